@@ -0,0 +1,3 @@
+//! Tools to study the strength of the cipher
+
+pub mod keys;
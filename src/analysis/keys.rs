@@ -0,0 +1,165 @@
+//! Weak and equivalent key analysis.
+//!
+//! Key expansion adds IV to the last value of the key stream, so after the first chunk
+//! the stream is `k3 + iv, k3 + 2 * iv, ...` and key parts 0 to 2 only affect
+//! the first chunk. Credentials with equal last key part and IV are equivalent
+//! for everything after it
+
+use crate::cipher::CHUNK_SIZE;
+use crate::error::CipherError;
+use crate::key::{Credentials, verify_encoding_size, verify_key};
+use std::collections::HashSet;
+
+/// Known weakness of credentials
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weakness {
+    /// IV is 0: every key value after the first chunk is equal to the last key part
+    ZeroIV,
+    /// All key parts are equal, see [`Credentials::is_weak`]
+    EqualParts,
+    /// Key stream after the first chunk repeats every n values, n is less than
+    /// the encoding size
+    ShortCycle(u16),
+}
+
+/// Report of [`analyze`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyReport {
+    pub weaknesses: Vec<Weakness>,
+    /// Key stream after the first chunk repeats every `cycle_length` values
+    pub cycle_length: u16,
+    /// Amount of other credentials which give the same key stream after the first chunk
+    pub equivalent_keys: u64,
+}
+
+impl KeyReport {
+    pub fn is_weak(&self) -> bool {
+        !self.weaknesses.is_empty()
+    }
+}
+
+/// How key streams of two credentials relate, see [`equivalence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equivalence {
+    /// Same key stream
+    Identical,
+    /// Same key, so messages of one chunk are encrypted the same way
+    SameFirstChunk,
+    /// Same last key part and IV, so everything after the first chunk is encrypted
+    /// the same way
+    SameAfterFirstChunk,
+    Different,
+}
+
+/// Size of the keyspace for an encoding, see [`keyspace`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyspace {
+    /// Amount of all credentials
+    pub total: u64,
+    /// Amount of different key streams after the first chunk
+    pub classes: u64,
+}
+
+impl Keyspace {
+    /// Real strength of the key in bits, only counting different streams after the first chunk
+    pub fn effective_bits(&self) -> f64 {
+        (self.classes as f64).log2()
+    }
+}
+
+/// Find weaknesses of credentials for the encoding of given size
+/// # Example
+/// ```
+/// use tinystorm::analysis::keys::{Weakness, analyze};
+/// use tinystorm::key::Credentials;
+///
+/// let report = analyze(&Credentials::new(&[25, 21, 18, 40], 39), 42).unwrap();
+/// // 39 and 42 are both divisible by 3
+/// assert_eq!(report.weaknesses, vec![Weakness::ShortCycle(14)]);
+/// ```
+pub fn analyze(credentials: &Credentials, encoding_size: u16) -> Result<KeyReport, CipherError> {
+    verify_encoding_size(encoding_size)?;
+    if !verify_key(&credentials.key) {
+        return Err(CipherError::InvalidKey);
+    }
+    credentials.verify_credentials_size(encoding_size)?;
+
+    let iv = credentials.iv as u16;
+    let cycle_length = encoding_size / gcd(iv, encoding_size);
+
+    let mut weaknesses = Vec::new();
+    if iv == 0 {
+        weaknesses.push(Weakness::ZeroIV);
+    } else if cycle_length < encoding_size {
+        weaknesses.push(Weakness::ShortCycle(cycle_length));
+    }
+    if credentials.has_equal_parts() {
+        weaknesses.push(Weakness::EqualParts);
+    }
+
+    // Parts 0 to 2 can be anything
+    let equivalent_keys = (encoding_size as u64).pow(CHUNK_SIZE as u32 - 1) - 1;
+
+    Ok(KeyReport {
+        weaknesses,
+        cycle_length,
+        equivalent_keys,
+    })
+}
+
+/// Compare key streams of two credentials
+/// # Example
+/// ```
+/// use tinystorm::analysis::keys::{Equivalence, equivalence};
+/// use tinystorm::key::Credentials;
+///
+/// let a = Credentials::new(&[25, 21, 18, 40], 39);
+/// let b = Credentials::new(&[1, 2, 3, 40], 39);
+/// assert_eq!(equivalence(&a, &b), Equivalence::SameAfterFirstChunk);
+/// ```
+pub fn equivalence(a: &Credentials, b: &Credentials) -> Equivalence {
+    let same_key = a.key == b.key;
+    let same_stream = a.key.last() == b.key.last() && a.iv == b.iv;
+
+    match (same_key, same_stream) {
+        (true, true) => Equivalence::Identical,
+        (true, false) => Equivalence::SameFirstChunk,
+        (false, true) => Equivalence::SameAfterFirstChunk,
+        (false, false) => Equivalence::Different,
+    }
+}
+
+/// Count different key streams after the first chunk over the whole keyspace
+/// of the encoding. Enumerates last key part and IV with the real key expansion
+/// # Example
+/// ```
+/// use tinystorm::analysis::keys::keyspace;
+///
+/// let keyspace = keyspace(42).unwrap();
+/// assert_eq!(keyspace.total, 42u64.pow(5));
+/// assert_eq!(keyspace.classes, 42 * 42);
+/// ```
+pub fn keyspace(encoding_size: u16) -> Result<Keyspace, CipherError> {
+    verify_encoding_size(encoding_size)?;
+
+    // Enough chunks to tell apart every stream
+    let chunks = 3;
+    let mut streams = HashSet::new();
+    let mut buffer = Vec::with_capacity(CHUNK_SIZE * chunks);
+    for last in 0..encoding_size {
+        for iv in 0..encoding_size {
+            let credentials = Credentials::new(&[0, 0, 0, last as u8], iv as u8);
+            credentials.expand_key(&mut buffer, chunks, encoding_size);
+            streams.insert(buffer[CHUNK_SIZE..].to_vec());
+        }
+    }
+
+    Ok(Keyspace {
+        total: (encoding_size as u64).pow(CHUNK_SIZE as u32 + 1),
+        classes: streams.len() as u64,
+    })
+}
+
+fn gcd(a: u16, b: u16) -> u16 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
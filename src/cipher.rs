@@ -1,63 +1,197 @@
-use crate::encoding::{DEFAULT_ENCODING, Encoder, Encoding, EncodingType};
+use crate::encoding::{CaseMask, DEFAULT_ENCODING, Encoder, Encoding, EncodingType, Registry};
+use crate::envelope::Envelope;
 use crate::error::CipherError;
-use crate::key::{Credentials, parse_credentials, verify_key};
+use crate::key::{Credentials, parse_credentials, verify_key, wipe};
+use crate::normalization::Normalization;
+use crate::transliteration::Transliteration;
+use std::borrow::Cow;
 
 pub const CHUNK_SIZE: usize = 4;
 
+/// Modulus of the byte mode, see [`Cipher::encrypt_bytes`]
+pub const BYTE_MODE_SIZE: u16 = 256;
+
+/// Newline in the ciphertext of line mode, see [`Cipher::encrypt_lines`]
+const NEWLINE_PICTURE: char = '␤';
+/// Carriage return in the ciphertext of line mode
+const CARRIAGE_RETURN_PICTURE: char = '␍';
+
 #[derive(Debug, PartialEq)]
 pub struct Cipher {
     encoder: Encoder,
     credentials: Credentials,
+    /// If set, encoding of the ciphertext is picked from its header
+    auto_encoding: Option<Registry>,
+    /// If set, ciphertexts always have header with encoding fingerprint
+    strict: bool,
 }
 
 impl Cipher {
-    /// Parse credentials as strings and create new Cipher with ENv1 Encoding
+    /// Parse credentials as strings and create new Cipher with ENv1 Encoding.
+    /// Key parts and IV must fit the encoding, see [`Cipher::with_encoding`] for other ones
     /// # Example
     /// ```
     /// use tinystorm::cipher::Cipher;
     ///
-    /// let cipher = Cipher::new("23091234", "89")
+    /// let cipher = Cipher::new("23091234", "39")
     ///     .unwrap();
+    /// // ENv1 has 42 symbols
+    /// assert!(Cipher::new("23091234", "89").is_err());
     /// ```
     pub fn new(key: &str, iv: &str) -> Result<Cipher, CipherError> {
-        let credentials = parse_credentials(key, iv)?;
-
-        Ok(Cipher {
-            encoder: Encoder::new(DEFAULT_ENCODING),
-            credentials,
-        })
+        Self::with_encoding(key, iv, DEFAULT_ENCODING)
     }
 
-    /// Create new Cipher from raw key and IV with default ENv1 encoding
+    /// Create new Cipher from raw key and IV with default ENv1 encoding. Returns an error
+    /// if key doesn't have 4 parts, or key parts or IV don't fit ENv1
     pub fn from(key: &[u8], iv: u8) -> Result<Cipher, CipherError> {
-        if !verify_key(key) {
+        Self::with_credentials(Credentials::new(key, iv), DEFAULT_ENCODING)
+    }
+
+    /// Parse credentials as strings and create new Cipher with given encoding
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::EncodingType;
+    ///
+    /// // IV 49 doesn't fit ENv1, but fits RUv5
+    /// let cipher = Cipher::with_encoding("23091234", "49", EncodingType::RUv5).unwrap();
+    /// ```
+    pub fn with_encoding(
+        key: &str,
+        iv: &str,
+        encoding_type: EncodingType,
+    ) -> Result<Cipher, CipherError> {
+        Self::with_credentials(parse_credentials(key, iv)?, encoding_type)
+    }
+
+    /// Create new Cipher from credentials with given encoding. Returns an error if
+    /// key doesn't have 4 parts, or key parts or IV don't fit the encoding
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::EncodingType;
+    /// use tinystorm::key::Credentials;
+    ///
+    /// let credentials = Credentials::generate(70).unwrap();
+    /// let cipher = Cipher::with_credentials(credentials, EncodingType::ENv2).unwrap();
+    /// ```
+    pub fn with_credentials(
+        credentials: Credentials,
+        encoding_type: EncodingType,
+    ) -> Result<Cipher, CipherError> {
+        if !verify_key(&credentials.key) {
             return Err(CipherError::InvalidKey);
         }
 
+        let encoder = Encoder::new(encoding_type);
+        credentials.verify_credentials_size(encoder.size)?;
+
         Ok(Cipher {
-            encoder: Encoder::new(DEFAULT_ENCODING),
-            credentials: Credentials {
-                key: key.to_vec(),
-                iv,
-            },
+            encoder,
+            credentials,
+            auto_encoding: None,
+            strict: false,
         })
     }
 
-    /// Change encoding (ENv1) to other
+    /// Change encoding (ENv1) to other. Returns an error and keeps the current encoding
+    /// if credentials don't fit the new one
     /// # Example
     /// ```
     /// use tinystorm::cipher::Cipher;
     /// use tinystorm::encoding::EncodingType;
+    /// use tinystorm::error::CipherError;
     ///
-    /// let mut cipher = Cipher::new("23091234", "89")
+    /// let mut cipher = Cipher::new("23091234", "41")
     ///     .unwrap();
-    /// cipher.set_encoder(EncodingType::RUv5);
+    /// cipher.set_encoder(EncodingType::RUv5).unwrap();
+    ///
+    /// // RUv4 has 41 symbols
+    /// assert_eq!(
+    ///     cipher.set_encoder(EncodingType::RUv4),
+    ///     Err(CipherError::IVOutOfBounds(41))
+    /// );
+    /// ```
+    pub fn set_encoder(&mut self, encoding_type: EncodingType) -> Result<(), CipherError> {
+        self.replace_encoder(Encoder::new(encoding_type))
+    }
+
+    /// Set normalization for chars that don't exist in the encoding,
+    /// see [`Encoder::set_normalization`]
+    /// # Example
     /// ```
-    pub fn set_encoder(&mut self, encoding_type: EncodingType) {
-        self.encoder = Encoder::new(encoding_type)
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::normalization::Normalization;
+    ///
+    /// let mut cipher = Cipher::new("25211840", "39").unwrap();
+    /// cipher.set_normalization(Normalization::Decompose);
+    ///
+    /// let ciphertext = cipher.encrypt("Crème brûlée").unwrap();
+    /// let decrypted = cipher.decrypt(&ciphertext).unwrap();
+    /// assert_eq!(decrypted.trim(), "creme brulee");
+    /// ```
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        self.encoder.set_normalization(normalization);
+    }
+
+    /// Set transliteration for chars that don't exist in the encoding,
+    /// see [`Encoder::set_transliteration`]
+    pub fn set_transliteration(&mut self, transliteration: Transliteration) {
+        self.encoder.set_transliteration(transliteration);
     }
 
-    /// Change encoding to your custom one
+    /// Split plaintext into grapheme clusters instead of chars,
+    /// see [`Encoder::set_grapheme_clusters`]. Ciphertext is always read symbol by symbol
+    pub fn set_grapheme_clusters(&mut self, enabled: bool) {
+        self.encoder.set_grapheme_clusters(enabled);
+    }
+
+    /// Pick encoding for decryption from the ciphertext header, see [`Cipher::encrypt_with_header`].
+    /// Encoding names are resolved with given `registry`, ciphertexts without header are
+    /// decrypted with the encoding of the cipher. Pass `None` to disable
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::{EncodingType, Registry};
+    ///
+    /// let mut sender = Cipher::new("25211840", "39").unwrap();
+    /// sender.set_encoder(EncodingType::ENv2).unwrap();
+    /// let ciphertext = sender.encrypt_with_header("Hello!").unwrap();
+    ///
+    /// // Receiver doesn't know which encoding was used
+    /// let mut receiver = Cipher::new("25211840", "39").unwrap();
+    /// receiver.set_auto_encoding(Some(Registry::new()));
+    /// assert_eq!(receiver.decrypt(&ciphertext).unwrap().trim(), "Hello!");
+    /// ```
+    pub fn set_auto_encoding(&mut self, registry: Option<Registry>) {
+        self.auto_encoding = registry;
+    }
+
+    /// Enable strict mode: [`Cipher::encrypt`] puts the header with encoding fingerprint in front
+    /// of the ciphertext, and [`Cipher::decrypt`] rejects ciphertexts without fingerprint.
+    /// Ciphertext with fingerprint is never decrypted with a different encoding,
+    /// strict mode or not
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::EncodingType;
+    /// use tinystorm::error::CipherError;
+    ///
+    /// let mut cipher = Cipher::new("25211840", "39").unwrap();
+    /// cipher.set_strict(true);
+    /// let ciphertext = cipher.encrypt("hello").unwrap();
+    ///
+    /// cipher.set_encoder(EncodingType::ENv2).unwrap();
+    /// assert_eq!(cipher.decrypt(&ciphertext), Err(CipherError::EncodingMismatch));
+    /// assert_eq!(cipher.decrypt("abcd"), Err(CipherError::MissingFingerprint));
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Change encoding to your custom one. Returns an error and keeps the current encoding
+    /// if credentials don't fit the new one
     /// # Example
     /// ```
     /// use tinystorm::cipher::Cipher;
@@ -70,40 +204,50 @@ impl Cipher {
     ///     ('c', 3),
     /// ]);
     ///
-    /// let cipher = Cipher::from(&[1, 2, 3, 4], 5)
-    ///     .unwrap()
-    ///     .load_encoder(MY_TABLE, false);
+    /// let mut cipher = Cipher::from(&[1, 0, 2, 1], 2).unwrap();
+    /// cipher.load_encoder(MY_TABLE, false).unwrap();
+    /// assert_eq!(cipher.get_encoding_bounds(), 3);
+    ///
+    /// // Key part 25 doesn't fit the table of 3 symbols
+    /// let mut cipher = Cipher::from(&[25, 21, 18, 40], 39).unwrap();
+    /// assert!(cipher.load_encoder(MY_TABLE, false).is_err());
+    /// assert_eq!(cipher.get_encoding_bounds(), 42);
     /// ```
     pub fn load_encoder(
         &mut self,
         encoding: Encoding,
         supports_uppercase: bool,
     ) -> Result<(), CipherError> {
-        self.encoder = Encoder::load(encoding, supports_uppercase)?;
-        Ok(())
+        self.replace_encoder(Encoder::load(encoding, supports_uppercase)?)
+    }
+
+    /// Encrypt given plaintext and put the header with the encoding name and fingerprint
+    /// in front of it, see [`Envelope`]. Name is omitted if encoding doesn't have one.
+    /// Fingerprint reveals keyed tables to keyword guessing, see [`Encoding::keyed`]
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    ///
+    /// let cipher = Cipher::new("25211840", "39").unwrap();
+    /// let ciphertext = cipher.encrypt_with_header("hello, world!").unwrap();
+    /// assert!(ciphertext.starts_with("[enc=ENv1;fp="));
+    /// assert!(ciphertext.ends_with("]yd 0yc ehyrhjgdd"));
+    /// ```
+    pub fn encrypt_with_header(&self, plaintext: &str) -> Result<String, CipherError> {
+        let ciphertext = self.encrypt(plaintext)?;
+
+        if self.strict {
+            // Already has the header
+            return Ok(ciphertext);
+        }
+        Ok(self.envelope().wrap(&ciphertext))
     }
 
     /// Raw encryption function.
     /// Warning! If your buffer is can't be divided by CHUNK_SIZE without
     /// remainder, it can be truncated. Not recommended to use
     pub fn encrypt_raw(&self, buffer: &mut [u8]) {
-        let chunks = buffer.chunks_exact_mut(CHUNK_SIZE);
-
-        // Expanding key
-        let key_capacity = chunks.len();
-        let mut key = Vec::with_capacity(key_capacity);
-        self.credentials
-            .expand_key(&mut key, key_capacity, self.encoder.size);
-
-        // Swap chunks
-        for chunk in chunks {
-            swap_chunk(chunk, self.encoder.size)
-        }
-
-        // Add key
-        for (d, s) in buffer.iter_mut().zip(key.iter()) {
-            *d = (*d + *s) % self.encoder.size;
-        }
+        self.encrypt_raw_with(buffer, self.encoder.size);
     }
 
     /// Encrypt given plaintext
@@ -118,20 +262,7 @@ impl Cipher {
     /// println!("Ciphertext: {}", ciphertext); //xc-zu9ari !88
     /// ```
     pub fn encrypt(&self, plaintext: &str) -> Result<String, CipherError> {
-        // Verifying size of our credentials
-        self.credentials
-            .verify_credentials_size(self.encoder.size)?;
-
-        // Step 1: Encode
-        let mut encoded = self.encoder.encode(plaintext);
-
-        // Step 2: Adjust chunks
-        let remainder = encoded.len() % CHUNK_SIZE;
-        let adjusted = adjust_chunks(&mut encoded, remainder);
-
-        self.encrypt_raw(adjusted);
-
-        Ok(self.encoder.decode(adjusted))
+        Ok(self.seal(self.encrypt_body(plaintext)?))
     }
 
     /// Decrypt given ciphertext
@@ -149,91 +280,403 @@ impl Cipher {
     /// assert_eq!(decrypted.trim(), plaintext);
     /// ```
     pub fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
-        // Verifying size of our credentials
+        let (encoder, encoded) = self.decrypt_to_symbols(ciphertext)?;
+        Ok(encoder.decode(&encoded))
+    }
+
+    /// Encrypt given plaintext line by line, so the ciphertext keeps the line structure of
+    /// the plaintext and encrypted files can still be compared line by line.
+    /// Each line is padded to full chunks on its own, empty lines stay empty
+    /// and line endings ("\n" or "\r\n") are kept as is. In strict mode the header goes
+    /// in front of the first line.
+    ///
+    /// Document tables like ENv3 can produce newline in the ciphertext, so in line mode
+    /// ciphertext newlines are written as '␤' and carriage returns as '␍'.
+    ///
+    /// Note that every line is encrypted from the start of the key stream, so equal lines
+    /// give equal ciphertexts
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    ///
+    /// let plaintext = "first line\n\nthird line\r\n";
+    /// let cipher = Cipher::new("25211840", "39").unwrap();
+    ///
+    /// let ciphertext = cipher.encrypt_lines(plaintext).unwrap();
+    /// assert_eq!(ciphertext.lines().count(), 3);
+    ///
+    /// let decrypted = cipher.decrypt_lines(&ciphertext).unwrap();
+    /// assert_eq!(decrypted, plaintext);
+    /// ```
+    pub fn encrypt_lines(&self, plaintext: &str) -> Result<String, CipherError> {
+        let lines = map_lines(plaintext, |line| {
+            let ciphertext = self.encrypt_body(line)?;
+            Ok(ciphertext
+                .chars()
+                .map(|c| match c {
+                    '\n' => NEWLINE_PICTURE,
+                    '\r' => CARRIAGE_RETURN_PICTURE,
+                    c => c,
+                })
+                .collect())
+        })?;
+        Ok(self.seal(lines))
+    }
+
+    /// Decrypt ciphertext encrypted with [`Cipher::encrypt_lines`]. Padding at the end of
+    /// each line is removed, so trailing whitespaces of the plaintext lines are lost
+    pub fn decrypt_lines(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let (envelope, body) = Envelope::split(ciphertext);
+        let encoder = self.pick_encoder(envelope)?;
+
+        map_lines(body, |line| {
+            let line = line
+                .chars()
+                .map(|c| match c {
+                    NEWLINE_PICTURE => '\n',
+                    CARRIAGE_RETURN_PICTURE => '\r',
+                    c => c,
+                })
+                .collect::<String>();
+            let decrypted = encoder.decode(&self.decrypt_body(&encoder, &line)?);
+
+            // Strip the padding
+            match encoder.table.char_of(0) {
+                Some(padding) => Ok(decrypted.trim_end_matches(padding).to_owned()),
+                None => Ok(decrypted),
+            }
+        })
+    }
+
+    /// Encrypt arbitrary binary data in byte mode: symbols are bytes and modulus is 256,
+    /// regardless of the encoding of the cipher. Data is padded to full chunks with
+    /// 1 to 4 bytes, each of them equal to the padding length
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::EncodingType;
+    ///
+    /// // In byte mode key parts can be written with 3 digits each, up to 255
+    /// let cipher = Cipher::with_encoding("025210008240", "139", EncodingType::Bytes).unwrap();
+    ///
+    /// let data = [0x89, b'P', b'N', b'G', 0x00, 0xff];
+    /// let encrypted = cipher.encrypt_bytes(&data).unwrap();
+    /// assert_eq!(encrypted.len(), 8);
+    ///
+    /// let decrypted = cipher.decrypt_bytes(&encrypted).unwrap();
+    /// assert_eq!(decrypted, data);
+    /// ```
+    pub fn encrypt_bytes(&self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+        self.credentials.verify_credentials_size(BYTE_MODE_SIZE)?;
+
+        let padding = CHUNK_SIZE - data.len() % CHUNK_SIZE;
+        let mut buffer = Vec::with_capacity(data.len() + padding);
+        buffer.extend_from_slice(data);
+        buffer.resize(data.len() + padding, padding as u8);
+
+        self.encrypt_raw_with(&mut buffer, BYTE_MODE_SIZE);
+        Ok(buffer)
+    }
+
+    /// Decrypt data encrypted with [`Cipher::encrypt_bytes`] and remove the padding
+    pub fn decrypt_bytes(&self, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+        self.credentials.verify_credentials_size(BYTE_MODE_SIZE)?;
+
+        if data.is_empty() || !data.len().is_multiple_of(CHUNK_SIZE) {
+            return Err(CipherError::InvalidPadding);
+        }
+
+        let mut buffer = data.to_vec();
+        self.decrypt_raw_with(&mut buffer, BYTE_MODE_SIZE);
+
+        // Every padding byte is equal to the padding length
+        let padding = buffer[buffer.len() - 1] as usize;
+        let is_valid = (1..=CHUNK_SIZE).contains(&padding)
+            && buffer[buffer.len() - padding..]
+                .iter()
+                .all(|&b| b as usize == padding);
+        if !is_valid {
+            return Err(CipherError::InvalidPadding);
+        }
+
+        buffer.truncate(buffer.len() - padding);
+        Ok(buffer)
+    }
+
+    /// Encrypt given plaintext keeping track of its uppercase chars.
+    /// Encodings like ENv1 or RUv5 don't support uppercase, so the case is lost on
+    /// regular encryption. Store returned [`CaseMask`] next to the ciphertext and pass it to
+    /// [`Cipher::decrypt_with_case`] to get the exact plaintext back
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    ///
+    /// let plaintext = "Hello, World!";
+    /// let cipher = Cipher::new("25211840", "39").unwrap();
+    ///
+    /// let (ciphertext, mask) = cipher.encrypt_preserving_case(plaintext).unwrap();
+    /// let decrypted = cipher.decrypt_with_case(&ciphertext, &mask).unwrap();
+    /// assert_eq!(decrypted.trim(), plaintext);
+    /// ```
+    pub fn encrypt_preserving_case(
+        &self,
+        plaintext: &str,
+    ) -> Result<(String, CaseMask), CipherError> {
         self.credentials
             .verify_credentials_size(self.encoder.size)?;
 
-        // As well, encode
-        let mut encoded = self.encoder.encode(ciphertext);
+        let (mut encoded, mask) = self.encoder.encode_preserving_case(plaintext);
+
+        let remainder = encoded.len() % CHUNK_SIZE;
+        let adjusted = adjust_chunks(&mut encoded, remainder);
+
+        self.encrypt_raw(adjusted);
+
+        Ok((self.seal(self.encoder.write_ciphertext(adjusted)), mask))
+    }
+
+    /// Decrypt given ciphertext and restore uppercase chars from `mask`,
+    /// see [`Cipher::encrypt_preserving_case`]
+    pub fn decrypt_with_case(
+        &self,
+        ciphertext: &str,
+        mask: &CaseMask,
+    ) -> Result<String, CipherError> {
+        let (encoder, encoded) = self.decrypt_to_symbols(ciphertext)?;
+        Ok(encoder.decode_with_case(&encoded, mask))
+    }
+
+    /// Raw decrypt function. Not recommended to use
+    pub fn decrypt_raw(&self, buffer: &mut [u8]) {
+        self.decrypt_raw_with(buffer, self.encoder.size);
+    }
+
+    pub fn get_encoding_bounds(&self) -> u16 {
+        self.encoder.size
+    }
+}
+
+// Private methods
+impl Cipher {
+    /// Switch to the new encoder if credentials fit it
+    fn replace_encoder(&mut self, encoder: Encoder) -> Result<(), CipherError> {
+        self.credentials.verify_credentials_size(encoder.size)?;
+        self.encoder = encoder;
+        Ok(())
+    }
+
+    /// Strip the header, pick the encoder and decrypt ciphertext to encoded symbols
+    pub(crate) fn decrypt_to_symbols(
+        &self,
+        ciphertext: &str,
+    ) -> Result<(Cow<'_, Encoder>, Vec<u8>), CipherError> {
+        let (envelope, body) = Envelope::split(ciphertext);
+        let encoder = self.pick_encoder(envelope)?;
+        let encoded = self.decrypt_body(&encoder, body)?;
+
+        Ok((encoder, encoded))
+    }
+
+    /// Pick the encoder for decryption by the header of the ciphertext
+    fn pick_encoder(&self, envelope: Option<Envelope>) -> Result<Cow<'_, Encoder>, CipherError> {
+        let Envelope {
+            encoding,
+            fingerprint,
+            ..
+        } = envelope.unwrap_or_default();
+
+        let encoder = match (&self.auto_encoding, encoding) {
+            (Some(registry), Some(name)) => Cow::Owned(registry.get(&name)?),
+            _ => Cow::Borrowed(&self.encoder),
+        };
+
+        match fingerprint {
+            Some(fingerprint) if fingerprint != encoder.table.fingerprint() => {
+                return Err(CipherError::EncodingMismatch);
+            }
+            None if self.strict => return Err(CipherError::MissingFingerprint),
+            _ => {}
+        }
+
+        Ok(encoder)
+    }
+
+    /// Decrypt ciphertext without header to encoded symbols
+    fn decrypt_body(&self, encoder: &Encoder, body: &str) -> Result<Vec<u8>, CipherError> {
+        // Verifying size of our credentials
+        self.credentials.verify_credentials_size(encoder.size)?;
+
+        // As well, read symbols of the ciphertext
+        let mut encoded = encoder.read_ciphertext(body);
 
         // We don't need to adjust the chunks here because if
         // ciphertext has malformed size, it is not our
         // problem =)
-        self.decrypt_raw(&mut encoded);
+        self.decrypt_raw_with(&mut encoded, encoder.size);
 
-        Ok(self.encoder.decode(&encoded))
+        Ok(encoded)
     }
 
-    /// Raw decrypt function. Not recommended to use
-    pub fn decrypt_raw(&self, buffer: &mut [u8]) {
+    /// Encrypt given plaintext without the header
+    fn encrypt_body(&self, plaintext: &str) -> Result<String, CipherError> {
+        // Verifying size of our credentials
+        self.credentials
+            .verify_credentials_size(self.encoder.size)?;
+
+        // Step 1: Encode
+        let mut encoded = self.encoder.encode(plaintext);
+
+        // Step 2: Adjust chunks
+        let remainder = encoded.len() % CHUNK_SIZE;
+        let adjusted = adjust_chunks(&mut encoded, remainder);
+
+        self.encrypt_raw(adjusted);
+
+        Ok(self.encoder.write_ciphertext(adjusted))
+    }
+
+    /// Encrypt already encoded symbols without the header. Symbols are padded only
+    /// if they don't fill whole chunks
+    pub(crate) fn encrypt_symbols(&self, mut symbols: Vec<u8>) -> Result<String, CipherError> {
+        self.credentials
+            .verify_credentials_size(self.encoder.size)?;
+
+        let remainder = symbols.len() % CHUNK_SIZE;
+        if remainder != 0 {
+            adjust_chunks(&mut symbols, remainder);
+        }
+
+        self.encrypt_raw(&mut symbols);
+        Ok(self.encoder.write_ciphertext(&symbols))
+    }
+
+    pub(crate) fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Header describing the encoding of the cipher
+    pub(crate) fn envelope(&self) -> Envelope {
+        Envelope {
+            key_id: None,
+            encoding: self.encoder.name().map(str::to_owned),
+            fingerprint: Some(self.encoder.table.fingerprint()),
+        }
+    }
+
+    /// Put the header in front of the ciphertext in strict mode
+    pub(crate) fn seal(&self, ciphertext: String) -> String {
+        if self.strict {
+            self.envelope().wrap(&ciphertext)
+        } else {
+            ciphertext
+        }
+    }
+
+    fn encrypt_raw_with(&self, buffer: &mut [u8], range_mod: u16) {
+        let chunks = buffer.chunks_exact_mut(CHUNK_SIZE);
+
+        // Expanding key
+        let key_capacity = chunks.len();
+        // Exact capacity, so the key isn't copied on reallocation
+        let mut key = Vec::with_capacity(key_capacity * CHUNK_SIZE);
+        self.credentials
+            .expand_key(&mut key, key_capacity, range_mod);
+
+        // Swap chunks
+        for chunk in chunks {
+            swap_chunk(chunk, range_mod)
+        }
+
+        // Add key
+        for (d, s) in buffer.iter_mut().zip(key.iter()) {
+            *d = add_mod(*d, *s, range_mod);
+        }
+
+        wipe(&mut key);
+    }
+
+    fn decrypt_raw_with(&self, buffer: &mut [u8], range_mod: u16) {
         // As well, expanding the key
         let key_capacity = buffer.len() / CHUNK_SIZE;
-        let mut key = Vec::new();
+        let mut key = Vec::with_capacity(key_capacity * CHUNK_SIZE);
         self.credentials
-            .expand_key(&mut key, key_capacity, self.encoder.size);
+            .expand_key(&mut key, key_capacity, range_mod);
 
         // Removing the key
         for (d, s) in buffer.iter_mut().zip(key.iter()) {
-            *d = if *d < *s { *d + self.encoder.size } else { *d } - *s;
+            *d = sub_mod(*d, *s, range_mod);
         }
+        wipe(&mut key);
 
         // Reverse chunk swap
         let chunks = buffer.chunks_exact_mut(CHUNK_SIZE);
         for chunk in chunks {
-            reverse_chunk_swap(chunk, self.encoder.size);
+            reverse_chunk_swap(chunk, range_mod);
         }
     }
+}
 
-    pub fn get_encoding_bounds(&self) -> u8 {
-        self.encoder.size
+/// Apply `f` to every non-empty line of `text`, keeping line endings
+fn map_lines(
+    text: &str,
+    mut f: impl FnMut(&str) -> Result<String, CipherError>,
+) -> Result<String, CipherError> {
+    let mut result = String::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if !content.is_empty() {
+            result.push_str(&f(content)?);
+        }
+        result.push_str(&line[content.len()..]);
     }
-}
 
-pub(crate) fn adjust_chunks(buffer: &mut [u8], remainder: usize) -> &mut [u8] {
-    let len = buffer.len();
+    Ok(result)
+}
 
-    // Use it only if you sure that slice points to vector that can accept adjust
-    unsafe {
-        let vec_ptr = buffer.as_mut_ptr();
-        let padding = 4 - remainder;
+/// Pad buffer with zeros up to the next chunk. Note that a full chunk of padding is added
+/// if `remainder` is zero
+pub(crate) fn adjust_chunks(buffer: &mut Vec<u8>, remainder: usize) -> &mut [u8] {
+    let padding = CHUNK_SIZE - remainder;
+    buffer.resize(buffer.len() + padding, 0);
+    buffer
+}
 
-        for i in 0..padding {
-            *vec_ptr.add(len + i) = 0;
-        }
+/// Addition in finite field. Computed in `u16`, so `range_mod` can be up to 256
+#[inline]
+pub(crate) fn add_mod(left: u8, right: u8, range_mod: u16) -> u8 {
+    ((left as u16 + right as u16) % range_mod) as u8
+}
 
-        std::slice::from_raw_parts_mut(vec_ptr, len + padding)
-    }
+/// Subtraction in finite field, reverse of [`add_mod`]
+#[inline]
+pub(crate) fn sub_mod(left: u8, right: u8, range_mod: u16) -> u8 {
+    // If left is less than right, we assume finite field reset
+    ((left as u16 + range_mod - right as u16) % range_mod) as u8
 }
 
-fn swap_chunk(buffer: &mut [u8], range_mod: u8) {
+fn swap_chunk(buffer: &mut [u8], range_mod: u16) {
     if buffer.len() < CHUNK_SIZE {
         return;
     }
 
     let [a, b, c, d] = [buffer[0], buffer[1], buffer[2], buffer[3]];
 
-    let ab = (a + b) % range_mod;
-    let cd = (c + d) % range_mod;
+    let ab = add_mod(a, b, range_mod);
+    let cd = add_mod(c, d, range_mod);
 
-    buffer[1] = (ab + c) % range_mod;
-    buffer[3] = (cd + b) % range_mod;
-    buffer[0] = (ab + buffer[3]) % range_mod;
-    buffer[2] = (cd + buffer[0]) % range_mod;
+    buffer[1] = add_mod(ab, c, range_mod);
+    buffer[3] = add_mod(cd, b, range_mod);
+    buffer[0] = add_mod(ab, buffer[3], range_mod);
+    buffer[2] = add_mod(cd, buffer[0], range_mod);
 }
 
-fn reverse_chunk_swap(buffer: &mut [u8], range_mod: u8) {
+fn reverse_chunk_swap(buffer: &mut [u8], range_mod: u16) {
     if buffer.len() < CHUNK_SIZE {
         return;
     }
 
-    let extract = |left: u8, right: u8| -> u8 {
-        // If left is less than right, we assume finite field reset
-        if left < right {
-            left + range_mod - right
-        } else {
-            left - right
-        }
-    };
+    let extract = |left: u8, right: u8| -> u8 { sub_mod(left, right, range_mod) };
 
     let [a, b, c, d] = [buffer[0], buffer[1], buffer[2], buffer[3]];
 
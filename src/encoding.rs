@@ -1,45 +1,685 @@
+use crate::envelope::is_valid_name;
 use crate::error::CipherError;
+use crate::grapheme::cluster_lengths;
+use crate::normalization::{Normalization, Normalized, normalize};
+use crate::transliteration::{Transliteration, transliterate};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fmt::Display;
+use std::str::FromStr;
 
 pub const DEFAULT_ENCODING: EncodingType = EncodingType::ENv1;
-#[derive(Debug, Clone)]
+
+/// Written between ciphertext symbols of tables with sequences where the next symbols
+/// would be read as a longer sequence, like "c" and "h" next to each other with "ch"
+/// in the table. Such tables can't have it as a symbol
+pub const SYMBOL_SEPARATOR: char = '·';
+/// Built-in encoding tables. Can be parsed from its name ("ENv2") or ISO 639-1 code of
+/// the language ("en", "uk"), the code resolves to the latest table of the language
+/// which is not a document table
+/// # Example
+/// ```
+/// use tinystorm::encoding::EncodingType;
+///
+/// assert_eq!("ENv2".parse(), Ok(EncodingType::ENv2));
+/// assert_eq!("ru".parse(), Ok(EncodingType::RUv5));
+/// assert_eq!(EncodingType::UKv1.to_string(), "UKv1");
+///
+/// for encoding in EncodingType::list() {
+///     println!("{encoding} ({:?})", encoding.language());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EncodingType {
     RUv4,
     RUv5,
     ENv1,
     ENv2,
+    UKv1,
+    BEv1,
+    DEv1,
+    FRv1,
+    ESv1,
+    PLv1,
+    ELv1,
+    /// Document table: ENv2 with newline, tab, quotes and brackets
+    ENv3,
+    /// Document table: RUv5 with uppercase, newline, tab, quotes and brackets
+    RUv6,
+    /// Byte mode: 256 symbols, each char from U+0000 to U+00FF is encoded as its byte
+    Bytes,
+}
+
+const ENCODING_TYPES: [EncodingType; 14] = [
+    EncodingType::RUv4,
+    EncodingType::RUv5,
+    EncodingType::ENv1,
+    EncodingType::ENv2,
+    EncodingType::UKv1,
+    EncodingType::BEv1,
+    EncodingType::DEv1,
+    EncodingType::FRv1,
+    EncodingType::ESv1,
+    EncodingType::PLv1,
+    EncodingType::ELv1,
+    EncodingType::ENv3,
+    EncodingType::RUv6,
+    EncodingType::Bytes,
+];
+
+impl EncodingType {
+    /// All built-in encodings, older versions go first
+    pub fn list() -> &'static [EncodingType] {
+        &ENCODING_TYPES
+    }
+
+    /// Name of the encoding, like "ENv2"
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncodingType::RUv4 => "RUv4",
+            EncodingType::RUv5 => "RUv5",
+            EncodingType::ENv1 => "ENv1",
+            EncodingType::ENv2 => "ENv2",
+            EncodingType::UKv1 => "UKv1",
+            EncodingType::BEv1 => "BEv1",
+            EncodingType::DEv1 => "DEv1",
+            EncodingType::FRv1 => "FRv1",
+            EncodingType::ESv1 => "ESv1",
+            EncodingType::PLv1 => "PLv1",
+            EncodingType::ELv1 => "ELv1",
+            EncodingType::ENv3 => "ENv3",
+            EncodingType::RUv6 => "RUv6",
+            EncodingType::Bytes => "Bytes",
+        }
+    }
+
+    /// Encoding table of the built-in encoding
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            EncodingType::RUv4 => ENCODING_RUV4,
+            EncodingType::RUv5 => ENCODING_RUV5,
+            EncodingType::ENv1 => ENCODING_ENV1,
+            EncodingType::ENv2 => ENCODING_ENV2,
+            EncodingType::UKv1 => ENCODING_UKV1,
+            EncodingType::BEv1 => ENCODING_BEV1,
+            EncodingType::DEv1 => ENCODING_DEV1,
+            EncodingType::FRv1 => ENCODING_FRV1,
+            EncodingType::ESv1 => ENCODING_ESV1,
+            EncodingType::PLv1 => ENCODING_PLV1,
+            EncodingType::ELv1 => ENCODING_ELV1,
+            EncodingType::ENv3 => ENCODING_ENV3,
+            EncodingType::RUv6 => ENCODING_RUV6,
+            EncodingType::Bytes => ENCODING_BYTES,
+        }
+    }
+
+    /// Returns true if the encoding has uppercase chars, otherwise text is converted
+    /// to lowercase by encoder
+    pub fn supports_uppercase(&self) -> bool {
+        matches!(
+            self,
+            EncodingType::ENv2 | EncodingType::ENv3 | EncodingType::RUv6 | EncodingType::Bytes
+        )
+    }
+
+    /// Returns true for document tables, which can encode multi-line text.
+    /// Language codes don't resolve to them
+    pub fn is_document(&self) -> bool {
+        matches!(self, EncodingType::ENv3 | EncodingType::RUv6)
+    }
+
+    /// ISO 639-1 code of the language, byte mode doesn't have one
+    pub fn language(&self) -> Option<&'static str> {
+        match self {
+            EncodingType::RUv4 | EncodingType::RUv5 | EncodingType::RUv6 => Some("ru"),
+            EncodingType::ENv1 | EncodingType::ENv2 | EncodingType::ENv3 => Some("en"),
+            EncodingType::UKv1 => Some("uk"),
+            EncodingType::BEv1 => Some("be"),
+            EncodingType::DEv1 => Some("de"),
+            EncodingType::FRv1 => Some("fr"),
+            EncodingType::ESv1 => Some("es"),
+            EncodingType::PLv1 => Some("pl"),
+            EncodingType::ELv1 => Some("el"),
+            EncodingType::Bytes => None,
+        }
+    }
+}
+
+impl Display for EncodingType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for EncodingType {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let by_name = ENCODING_TYPES
+            .iter()
+            .find(|encoding| encoding.name().eq_ignore_ascii_case(s));
+        // Latest version of the language is the last one in the list
+        let by_language = || {
+            ENCODING_TYPES.iter().rev().find(|encoding| {
+                !encoding.is_document()
+                    && encoding
+                        .language()
+                        .is_some_and(|l| l.eq_ignore_ascii_case(s))
+            })
+        };
+
+        by_name
+            .or_else(by_language)
+            .copied()
+            .ok_or_else(|| CipherError::UnknownEncoding(s.to_owned()))
+    }
 }
 
 /// Encoding table represented as static array of (Char, encoded number). Can be created only with
 /// [`encoding_table`] macro to perform compile-time checks
 ///
+/// Table can also have sequences: (String, encoded number) pairs for symbols that consist of
+/// several chars, like "ch" or "\r\n". Encoder always picks the longest sequence that matches,
+/// so a table can contain both 'c' and "ch"
+///
+/// And aliases: (Alias, Char) pairs. Alias is accepted by encoder as the char it points to,
+/// but never produced by decoder
+///
 /// [`encoding_table`]: crate::encoding_table
-#[derive(Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Encoding {
-    inner: &'static [(char, u8)],
+    inner: Cow<'static, [(char, u8)]>,
+    sequences: Cow<'static, [(&'static str, u8)]>,
+    aliases: Cow<'static, [(char, char)]>,
+    /// Symbol order of keyed tables is a part of the secret, see [`Encoding::keyed`]
+    keyed: bool,
+}
+
+impl std::fmt::Debug for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if !self.keyed {
+            return f
+                .debug_struct("Encoding")
+                .field("inner", &self.inner)
+                .field("sequences", &self.sequences)
+                .field("aliases", &self.aliases)
+                .finish();
+        }
+
+        f.debug_struct("Encoding")
+            .field("inner", &format_args!("<redacted>"))
+            .field("sequences", &format_args!("<redacted>"))
+            .field("aliases", &self.aliases)
+            .field("keyed", &true)
+            .finish()
+    }
 }
 
 impl Encoding {
     #[doc(hidden)]
     /// Create new `Encoding` without compile-time check. Usage of this function is not recommended
     pub const fn new_unchecked(inner: &'static [(char, u8)]) -> Self {
-        Self { inner }
+        Self::from_parts_unchecked(inner, &[], &[])
+    }
+
+    #[doc(hidden)]
+    /// Create new `Encoding` with sequences and aliases without compile-time check.
+    /// Usage of this function is not recommended
+    pub const fn from_parts_unchecked(
+        inner: &'static [(char, u8)],
+        sequences: &'static [(&'static str, u8)],
+        aliases: &'static [(char, char)],
+    ) -> Self {
+        Self {
+            inner: Cow::Borrowed(inner),
+            sequences: Cow::Borrowed(sequences),
+            aliases: Cow::Borrowed(aliases),
+            keyed: false,
+        }
+    }
+
+    /// Create keyed (keyword-mixed) table from `base`, like in classical hand ciphers:
+    /// chars of the keyword go first without repeats, then all other symbols of the table
+    /// in their original order. Symbols are numbered by their new position, so the table
+    /// itself becomes a part of the secret. Keyword chars that don't exist in the table
+    /// are skipped, uppercase chars are converted to lowercase if the table doesn't have them.
+    /// Symbol with code 0 (space in built-in tables) is used for padding, so it keeps its code.
+    ///
+    /// Debug output of keyed tables (and encoders and ciphers with them) is redacted. Note that
+    /// the encoding fingerprint in ciphertext headers (see [`Encoding::fingerprint`]) depends on
+    /// the symbol order, so anyone can check guessed keywords against it. Don't use headers
+    /// or strict mode if the keyword must stay secret
+    /// # Example
+    /// ```
+    /// use tinystorm::cipher::Cipher;
+    /// use tinystorm::encoding::{Encoding, EncodingType};
+    ///
+    /// let keyed = Encoding::keyed(&EncodingType::ENv1.encoding(), "KRYPTOS");
+    ///
+    /// let mut cipher = Cipher::new("25211840", "39").unwrap();
+    /// cipher.load_encoder(keyed, false).unwrap();
+    ///
+    /// let ciphertext = cipher.encrypt("hello, world!").unwrap();
+    /// let decrypted = cipher.decrypt(&ciphertext).unwrap();
+    /// assert_eq!(decrypted.trim(), "hello, world!");
+    /// ```
+    pub fn keyed(base: &Encoding, keyword: &str) -> Encoding {
+        let code_of = |c: char| base.inner.iter().find(|&&(ch, _)| ch == c).map(|&(_, n)| n);
+
+        // Codes of the base table in the new order
+        let mut order = Vec::with_capacity(base.size() as usize);
+        order.push(0);
+        for c in keyword.chars() {
+            let lowercase = c.to_lowercase().next().unwrap_or(c);
+            if let Some(n) = code_of(c).or_else(|| code_of(lowercase))
+                && !order.contains(&n)
+            {
+                order.push(n);
+            }
+        }
+
+        let mut rest = base
+            .inner
+            .iter()
+            .map(|&(_, n)| n)
+            .chain(base.sequences.iter().map(|&(_, n)| n))
+            .filter(|n| !order.contains(n))
+            .collect::<Vec<_>>();
+        rest.sort_unstable();
+        order.append(&mut rest);
+
+        let new_code = |n: u8| order.iter().position(|&code| code == n).unwrap_or(0) as u8;
+        let inner = base
+            .inner
+            .iter()
+            .map(|&(c, n)| (c, new_code(n)))
+            .collect::<Vec<_>>();
+        let sequences = base
+            .sequences
+            .iter()
+            .map(|&(sequence, n)| (sequence, new_code(n)))
+            .collect::<Vec<_>>();
+
+        Encoding {
+            inner: Cow::Owned(inner),
+            sequences: Cow::Owned(sequences),
+            aliases: base.aliases.clone(),
+            keyed: true,
+        }
+    }
+
+    /// Iterate over single char symbols of the table as (Char, encoded number) pairs.
+    /// Aliases are not included, see [`Encoding::sequences`] for multi-char symbols
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::EncodingType;
+    ///
+    /// let table = EncodingType::ENv1.encoding();
+    /// for (c, n) in table.iter() {
+    ///     println!("{c:?} = {n}");
+    /// }
+    /// assert_eq!(table.len(), 42);
+    /// assert!(table.contains('z'));
+    /// assert!(!table.contains('Z'));
+    /// assert_eq!(table.code_of('a'), Some(1));
+    /// assert_eq!(table.char_of(1), Some('a'));
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (char, u8)> + '_ {
+        self.inner.iter().copied()
+    }
+
+    /// Iterate over multi-char symbols of the table as (String, encoded number) pairs
+    pub fn sequences(&self) -> impl Iterator<Item = (&'static str, u8)> + '_ {
+        self.sequences.iter().copied()
+    }
+
+    /// Amount of symbols in the table, including sequences. Aliases are not counted
+    pub fn len(&self) -> usize {
+        self.inner.len() + self.sequences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if char can be encoded by the table, either directly or as alias
+    pub fn contains(&self, c: char) -> bool {
+        self.code_of(c).is_some()
+    }
+
+    /// Encoded number of the char. Alias resolves to the number of the char it points to
+    pub fn code_of(&self, c: char) -> Option<u8> {
+        let lookup = |c: char| self.inner.iter().find(|&&(ch, _)| ch == c).map(|&(_, n)| n);
+
+        lookup(c).or_else(|| {
+            // Alias points to the char which is encoded instead
+            self.aliases
+                .iter()
+                .find(|&&(alias, _)| alias == c)
+                .and_then(|&(_, target)| lookup(target))
+        })
+    }
+
+    /// Char with given encoded number. Returns `None` if there is no such number,
+    /// or it belongs to a sequence, see [`Encoding::sequence_of`]
+    pub fn char_of(&self, code: u8) -> Option<char> {
+        self.inner
+            .iter()
+            .find(|&&(_, n)| n == code)
+            .map(|&(c, _)| c)
+    }
+
+    /// Multi-char symbol with given encoded number
+    pub fn sequence_of(&self, code: u8) -> Option<&'static str> {
+        self.sequences
+            .iter()
+            .find(|&&(_, n)| n == code)
+            .map(|&(sequence, _)| sequence)
+    }
+
+    /// Stable fingerprint of the table, computed from its symbols in order of their numbers
+    /// (32-bit FNV-1a). Tables with the same symbols in the same order have the same fingerprint,
+    /// aliases are not taken into account
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::EncodingType;
+    ///
+    /// let env1 = EncodingType::ENv1.encoding().fingerprint();
+    /// let ruv5 = EncodingType::RUv5.encoding().fingerprint();
+    /// assert_ne!(env1, ruv5);
+    /// println!("{env1:08x}");
+    /// ```
+    pub fn fingerprint(&self) -> u32 {
+        const FNV_OFFSET: u32 = 0x811c9dc5;
+        const FNV_PRIME: u32 = 0x01000193;
+
+        let mut hash = FNV_OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash ^= b as u32;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        for symbol in self.symbols() {
+            let mut buffer = [0; 4];
+            match symbol {
+                Symbol::Char(c) => feed(c.encode_utf8(&mut buffer).as_bytes()),
+                Symbol::Sequence(sequence) => feed(sequence.as_bytes()),
+            }
+            // Separator, so "ab" + "c" differs from "a" + "bc"
+            feed(&[0xff]);
+        }
+
+        hash
+    }
+
+    /// Add chars to the end of the table. Combinators allocate, so they can't be used
+    /// in const context: use [`encoding_table`] macro for const tables
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    ///
+    /// // ENv2 plus newline and tab
+    /// let table = EncodingType::ENv2.encoding().extend(&['\n', '\t']).unwrap();
+    /// let encoder = Encoder::load(table, true).unwrap();
+    /// assert_eq!(encoder.size, 72);
+    ///
+    /// // Chars must be unique
+    /// assert!(EncodingType::ENv2.encoding().extend(&['a']).is_err());
+    /// ```
+    ///
+    /// [`encoding_table`]: crate::encoding_table
+    pub fn extend(&self, chars: &[char]) -> Result<Encoding, CipherError> {
+        let mut symbols = self.symbols();
+        symbols.extend(chars.iter().map(|&c| Symbol::Char(c)));
+
+        Encoding::from_symbols(symbols, self.aliases.to_vec(), self.keyed)
+    }
+
+    /// Union of two tables: all symbols of `a`, then symbols of `b` which `a` doesn't have.
+    /// Symbols are renumbered by their position. Aliases of `b` are kept if they don't
+    /// conflict with `a`
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, Encoding, EncodingType};
+    ///
+    /// let table = Encoding::merge(
+    ///     &EncodingType::ENv1.encoding(),
+    ///     &EncodingType::RUv5.encoding(),
+    /// )
+    /// .unwrap();
+    /// let encoder = Encoder::load(table, false).unwrap();
+    ///
+    /// let encoded = encoder.encode("hi, привет!");
+    /// assert_eq!(encoder.decode(&encoded), "hi, привет!");
+    /// ```
+    pub fn merge(a: &Encoding, b: &Encoding) -> Result<Encoding, CipherError> {
+        let mut symbols = a.symbols();
+        for symbol in b.symbols() {
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+
+        let is_taken = |c: char| symbols.contains(&Symbol::Char(c));
+        let mut aliases = a.aliases.to_vec();
+        for &(alias, target) in b.aliases.iter() {
+            if !is_taken(alias) && !aliases.iter().any(|&(a, _)| a == alias) {
+                aliases.push((alias, target));
+            }
+        }
+
+        Encoding::from_symbols(symbols, aliases, a.keyed || b.keyed)
+    }
+
+    /// Keep only chars for which `filter` returns true. Sequences are kept if all of their
+    /// chars are kept, aliases if the char they point to is kept.
+    /// Symbols are renumbered by their position
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    ///
+    /// // ENv1 without digits
+    /// let table = EncodingType::ENv1
+    ///     .encoding()
+    ///     .subset(|c| !c.is_ascii_digit())
+    ///     .unwrap();
+    /// let encoder = Encoder::load(table, false).unwrap();
+    /// assert_eq!(encoder.size, 32);
+    /// ```
+    pub fn subset(&self, filter: impl Fn(char) -> bool) -> Result<Encoding, CipherError> {
+        let symbols = self
+            .symbols()
+            .into_iter()
+            .filter(|symbol| match symbol {
+                Symbol::Char(c) => filter(*c),
+                Symbol::Sequence(sequence) => sequence.chars().all(&filter),
+            })
+            .collect::<Vec<_>>();
+
+        let aliases = self
+            .aliases
+            .iter()
+            .copied()
+            .filter(|&(_, target)| filter(target))
+            .collect();
+
+        Encoding::from_symbols(symbols, aliases, self.keyed)
+    }
+
+    fn size(&self) -> u16 {
+        self.len() as u16
     }
 }
 #[macro_export]
-/// Create new encoding with compile-time check
+/// Create new encoding with compile-time check. Sequences are matched by greedy longest match.
+/// In ciphertexts, symbols which would be read as a longer sequence are separated
+/// with [`SYMBOL_SEPARATOR`](crate::encoding::SYMBOL_SEPARATOR)
+/// # Example
+/// ```
+/// use tinystorm::encoding::Encoding;
+/// use tinystorm::encoding_table;
+///
+/// // "ch" is a single symbol, 'ё' will be encoded as 'е', and long dash as '-'
+/// const MY_TABLE: Encoding = encoding_table!(
+///     [(' ', 0), ('c', 1), ('h', 2), ('е', 3), ('-', 4)],
+///     sequences: [("ch", 5)],
+///     aliases: [('ё', 'е'), ('—', '-')]
+/// );
+/// ```
 macro_rules! encoding_table {
-    ($($enc:expr),*) => {{
-        const INNER: &'static [(char, u8)] = &$($enc),*;
+    (@or $value:expr; $default:expr) => {
+        $value
+    };
+    (@or ; $default:expr) => {
+        $default
+    };
+    ($enc:expr $(, sequences: $sequences:expr)? $(, aliases: $aliases:expr)? $(,)?) => {{
+        const INNER: &'static [(char, u8)] = &$enc;
+        const SEQUENCES: &'static [(&'static str, u8)] =
+            $crate::encoding_table!(@or $(&$sequences)?; &[]);
+        const ALIASES: &'static [(char, char)] = $crate::encoding_table!(@or $(&$aliases)?; &[]);
 
         const IS_VALID: bool = $crate::encoding::check_for_malformed_encoding(INNER);
         if !IS_VALID {
             panic!("Malformed encoding");
         }
 
-        Encoding::new_unchecked(INNER)
+        const ARE_SEQUENCES_VALID: bool =
+            $crate::encoding::check_for_malformed_sequences(INNER, SEQUENCES);
+        if !ARE_SEQUENCES_VALID {
+            panic!("Malformed encoding sequences");
+        }
+
+        const ARE_ALIASES_VALID: bool =
+            $crate::encoding::check_for_malformed_aliases(INNER, ALIASES);
+        if !ARE_ALIASES_VALID {
+            panic!("Malformed encoding aliases");
+        }
+
+        $crate::encoding::Encoding::from_parts_unchecked(INNER, SEQUENCES, ALIASES)
+    }};
+}
+
+#[macro_export]
+/// Create new encoding from str with compile-time check: each char is a symbol, numbered
+/// in order starting from 0. Sequences and aliases can be added as in [`encoding_table`]
+/// # Example
+/// ```
+/// use tinystorm::encoding::{Encoder, Encoding, EncodingType};
+/// use tinystorm::encoding_from_str;
+///
+/// // The same symbols as ENv1
+/// const MY_TABLE: Encoding = encoding_from_str!(" abcdefghijklmnopqrstuvwxyz0123456789.,!?-");
+/// assert_eq!(MY_TABLE.iter().collect::<Vec<_>>(), EncodingType::ENv1.encoding().iter().collect::<Vec<_>>());
+///
+/// const WITH_ALIASES: Encoding = encoding_from_str!(" абв-", aliases: [('—', '-')]);
+/// let encoder = Encoder::load(WITH_ALIASES, false).unwrap();
+/// assert_eq!(encoder.encode("а—б"), vec![1, 4, 2]);
+/// ```
+///
+/// Repeated chars are rejected at compile time:
+/// ```compile_fail
+/// use tinystorm::encoding::Encoding;
+/// use tinystorm::encoding_from_str;
+///
+/// const MY_TABLE: Encoding = encoding_from_str!(" abca");
+/// ```
+///
+/// [`encoding_table`]: crate::encoding_table
+macro_rules! encoding_from_str {
+    ($symbols:expr $(, $($rest:tt)*)?) => {{
+        const SYMBOLS: &str = $symbols;
+        const LEN: usize = $crate::encoding::char_count(SYMBOLS);
+        if LEN > u8::MAX as usize + 1 {
+            panic!("Encoding can't have more than 256 symbols");
+        }
+
+        const TABLE: [(char, u8); LEN] = $crate::encoding::table_from_str::<LEN>(SYMBOLS);
+        $crate::encoding_table!(TABLE $(, $($rest)*)?)
     }};
 }
 
+/// Typographic dashes, accepted by all built-in tables as regular '-'
+const DASH_ALIASES: [(char, char); 2] = [('—', '-'), ('–', '-')];
+
+/// Dashes and typographic quotes, for document tables
+const DOCUMENT_ALIASES: [(char, char); 6] = [
+    ('—', '-'),
+    ('–', '-'),
+    ('“', '"'),
+    ('”', '"'),
+    ('‘', '\''),
+    ('’', '\''),
+];
+
+/// Single symbol of the table, used to rebuild tables at runtime
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Symbol {
+    Char(char),
+    Sequence(&'static str),
+}
+
+// Private methods
+impl Encoding {
+    /// All symbols of the table ordered by their numbers
+    fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols = self
+            .inner
+            .iter()
+            .map(|&(c, n)| (n, Symbol::Char(c)))
+            .chain(
+                self.sequences
+                    .iter()
+                    .map(|&(sequence, n)| (n, Symbol::Sequence(sequence))),
+            )
+            .collect::<Vec<_>>();
+        symbols.sort_by_key(|&(n, _)| n);
+
+        symbols.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+
+    /// Build table from symbols, numbering them by position, and validate it
+    /// with the same checks as [`encoding_table`] macro does
+    ///
+    /// [`encoding_table`]: crate::encoding_table
+    fn from_symbols(
+        symbols: Vec<Symbol>,
+        aliases: Vec<(char, char)>,
+        keyed: bool,
+    ) -> Result<Encoding, CipherError> {
+        if symbols.is_empty() || symbols.len() > u8::MAX as usize + 1 {
+            return Err(CipherError::MalformedEncoding);
+        }
+
+        let mut inner = Vec::new();
+        let mut sequences = Vec::new();
+        for (n, symbol) in symbols.into_iter().enumerate() {
+            match symbol {
+                Symbol::Char(c) => inner.push((c, n as u8)),
+                Symbol::Sequence(sequence) => sequences.push((sequence, n as u8)),
+            }
+        }
+
+        let is_valid = check_for_malformed_encoding(&inner)
+            && check_for_malformed_sequences(&inner, &sequences)
+            && check_for_malformed_aliases(&inner, &aliases);
+        if !is_valid {
+            return Err(CipherError::MalformedEncoding);
+        }
+
+        Ok(Encoding {
+            inner: Cow::Owned(inner),
+            sequences: Cow::Owned(sequences),
+            aliases: Cow::Owned(aliases),
+            keyed,
+        })
+    }
+}
+
 /// Encoding table for english alphabet
 const ENCODING_ENV1: Encoding = encoding_table!([
     (' ', 0),
@@ -83,8 +723,8 @@ const ENCODING_ENV1: Encoding = encoding_table!([
     (',', 38),
     ('!', 39),
     ('?', 40),
-    ('-', 41)
-]);
+    ('-', 41),
+], aliases: DASH_ALIASES);
 
 /// Extended encoding table for english alphabet
 const ENCODING_ENV2: Encoding = encoding_table!([
@@ -158,7 +798,7 @@ const ENCODING_ENV2: Encoding = encoding_table!([
     ('-', 67),
     ('+', 68),
     ('@', 69),
-]);
+], aliases: DASH_ALIASES);
 
 /// Encoding table for russian alphabet
 const ENCODING_RUV5: Encoding = encoding_table!([
@@ -213,10 +853,97 @@ const ENCODING_RUV5: Encoding = encoding_table!([
     ('-', 48),
     ('+', 49),
     ('@', 50),
-]);
+], aliases: DASH_ALIASES);
 
-/// Encoding table for russian alphabet, but without digits (OLD)
-const ENCODING_RUV4: Encoding = encoding_table!([
+/// Document encoding table for english alphabet: ENv2 with newline, tab, quotes and brackets
+const ENCODING_ENV3: Encoding = encoding_table!([
+    (' ', 0),
+    ('a', 1),
+    ('b', 2),
+    ('c', 3),
+    ('d', 4),
+    ('e', 5),
+    ('f', 6),
+    ('g', 7),
+    ('h', 8),
+    ('i', 9),
+    ('j', 10),
+    ('k', 11),
+    ('l', 12),
+    ('m', 13),
+    ('n', 14),
+    ('o', 15),
+    ('p', 16),
+    ('q', 17),
+    ('r', 18),
+    ('s', 19),
+    ('t', 20),
+    ('u', 21),
+    ('v', 22),
+    ('w', 23),
+    ('x', 24),
+    ('y', 25),
+    ('z', 26),
+    ('A', 27),
+    ('B', 28),
+    ('C', 29),
+    ('D', 30),
+    ('E', 31),
+    ('F', 32),
+    ('G', 33),
+    ('H', 34),
+    ('I', 35),
+    ('J', 36),
+    ('K', 37),
+    ('L', 38),
+    ('M', 39),
+    ('N', 40),
+    ('O', 41),
+    ('P', 42),
+    ('Q', 43),
+    ('R', 44),
+    ('S', 45),
+    ('T', 46),
+    ('U', 47),
+    ('V', 48),
+    ('W', 49),
+    ('X', 50),
+    ('Y', 51),
+    ('Z', 52),
+    ('0', 53),
+    ('1', 54),
+    ('2', 55),
+    ('3', 56),
+    ('4', 57),
+    ('5', 58),
+    ('6', 59),
+    ('7', 60),
+    ('8', 61),
+    ('9', 62),
+    ('.', 63),
+    (',', 64),
+    ('!', 65),
+    ('?', 66),
+    ('-', 67),
+    ('+', 68),
+    ('@', 69),
+    ('\n', 70),
+    ('\t', 71),
+    ('"', 72),
+    ('\'', 73),
+    ('(', 74),
+    (')', 75),
+    ('[', 76),
+    (']', 77),
+    ('{', 78),
+    ('}', 79),
+    (':', 80),
+    (';', 81),
+], aliases: DOCUMENT_ALIASES);
+
+/// Document encoding table for russian alphabet: RUv5 with uppercase, newline, tab,
+/// quotes and brackets
+const ENCODING_RUV6: Encoding = encoding_table!([
     (' ', 0),
     ('а', 1),
     ('б', 2),
@@ -233,14 +960,116 @@ const ENCODING_RUV4: Encoding = encoding_table!([
     ('л', 13),
     ('м', 14),
     ('н', 15),
-    ('п', 16),
-    ('т', 17),
+    ('о', 16),
+    ('п', 17),
     ('р', 18),
-    ('о', 19),
-    ('у', 20),
-    ('с', 21),
-    ('х', 22),
-    ('ф', 23),
+    ('с', 19),
+    ('т', 20),
+    ('у', 21),
+    ('ф', 22),
+    ('х', 23),
+    ('ц', 24),
+    ('ч', 25),
+    ('ш', 26),
+    ('щ', 27),
+    ('ъ', 28),
+    ('ы', 29),
+    ('ь', 30),
+    ('э', 31),
+    ('ю', 32),
+    ('я', 33),
+    ('0', 34),
+    ('1', 35),
+    ('2', 36),
+    ('3', 37),
+    ('4', 38),
+    ('5', 39),
+    ('6', 40),
+    ('7', 41),
+    ('8', 42),
+    ('9', 43),
+    ('.', 44),
+    (',', 45),
+    ('!', 46),
+    ('?', 47),
+    ('-', 48),
+    ('+', 49),
+    ('@', 50),
+    ('А', 51),
+    ('Б', 52),
+    ('В', 53),
+    ('Г', 54),
+    ('Д', 55),
+    ('Е', 56),
+    ('Ё', 57),
+    ('Ж', 58),
+    ('З', 59),
+    ('И', 60),
+    ('Й', 61),
+    ('К', 62),
+    ('Л', 63),
+    ('М', 64),
+    ('Н', 65),
+    ('О', 66),
+    ('П', 67),
+    ('Р', 68),
+    ('С', 69),
+    ('Т', 70),
+    ('У', 71),
+    ('Ф', 72),
+    ('Х', 73),
+    ('Ц', 74),
+    ('Ч', 75),
+    ('Ш', 76),
+    ('Щ', 77),
+    ('Ъ', 78),
+    ('Ы', 79),
+    ('Ь', 80),
+    ('Э', 81),
+    ('Ю', 82),
+    ('Я', 83),
+    ('\n', 84),
+    ('\t', 85),
+    ('"', 86),
+    ('\'', 87),
+    ('(', 88),
+    (')', 89),
+    ('[', 90),
+    (']', 91),
+    ('{', 92),
+    ('}', 93),
+    (':', 94),
+    (';', 95),
+    ('«', 96),
+    ('»', 97),
+], aliases: DOCUMENT_ALIASES);
+
+/// Encoding table for russian alphabet, but without digits (OLD)
+const ENCODING_RUV4: Encoding = encoding_table!([
+    (' ', 0),
+    ('а', 1),
+    ('б', 2),
+    ('в', 3),
+    ('г', 4),
+    ('д', 5),
+    ('е', 6),
+    ('ё', 7),
+    ('ж', 8),
+    ('з', 9),
+    ('и', 10),
+    ('й', 11),
+    ('к', 12),
+    ('л', 13),
+    ('м', 14),
+    ('н', 15),
+    ('п', 16),
+    ('т', 17),
+    ('р', 18),
+    ('о', 19),
+    ('у', 20),
+    ('с', 21),
+    ('х', 22),
+    ('ф', 23),
     ('ш', 24),
     ('щ', 25),
     ('ч', 26),
@@ -258,40 +1087,439 @@ const ENCODING_RUV4: Encoding = encoding_table!([
     ('+', 38),
     ('-', 39),
     ('@', 40),
-]);
+], aliases: DASH_ALIASES);
+
+/// Encoding table for ukrainian alphabet
+const ENCODING_UKV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('а', 1),
+    ('б', 2),
+    ('в', 3),
+    ('г', 4),
+    ('ґ', 5),
+    ('д', 6),
+    ('е', 7),
+    ('є', 8),
+    ('ж', 9),
+    ('з', 10),
+    ('и', 11),
+    ('і', 12),
+    ('ї', 13),
+    ('й', 14),
+    ('к', 15),
+    ('л', 16),
+    ('м', 17),
+    ('н', 18),
+    ('о', 19),
+    ('п', 20),
+    ('р', 21),
+    ('с', 22),
+    ('т', 23),
+    ('у', 24),
+    ('ф', 25),
+    ('х', 26),
+    ('ц', 27),
+    ('ч', 28),
+    ('ш', 29),
+    ('щ', 30),
+    ('ь', 31),
+    ('ю', 32),
+    ('я', 33),
+    ('0', 34),
+    ('1', 35),
+    ('2', 36),
+    ('3', 37),
+    ('4', 38),
+    ('5', 39),
+    ('6', 40),
+    ('7', 41),
+    ('8', 42),
+    ('9', 43),
+    ('.', 44),
+    (',', 45),
+    ('!', 46),
+    ('?', 47),
+    ('-', 48),
+    ('+', 49),
+    ('@', 50),
+    ('\'', 51),
+], aliases: DASH_ALIASES);
+
+/// Encoding table for belarusian alphabet
+const ENCODING_BEV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('а', 1),
+    ('б', 2),
+    ('в', 3),
+    ('г', 4),
+    ('д', 5),
+    ('е', 6),
+    ('ё', 7),
+    ('ж', 8),
+    ('з', 9),
+    ('і', 10),
+    ('й', 11),
+    ('к', 12),
+    ('л', 13),
+    ('м', 14),
+    ('н', 15),
+    ('о', 16),
+    ('п', 17),
+    ('р', 18),
+    ('с', 19),
+    ('т', 20),
+    ('у', 21),
+    ('ў', 22),
+    ('ф', 23),
+    ('х', 24),
+    ('ц', 25),
+    ('ч', 26),
+    ('ш', 27),
+    ('ы', 28),
+    ('ь', 29),
+    ('э', 30),
+    ('ю', 31),
+    ('я', 32),
+    ('0', 33),
+    ('1', 34),
+    ('2', 35),
+    ('3', 36),
+    ('4', 37),
+    ('5', 38),
+    ('6', 39),
+    ('7', 40),
+    ('8', 41),
+    ('9', 42),
+    ('.', 43),
+    (',', 44),
+    ('!', 45),
+    ('?', 46),
+    ('-', 47),
+    ('+', 48),
+    ('@', 49),
+    ('\'', 50),
+], aliases: DASH_ALIASES);
+
+/// Encoding table for german alphabet
+const ENCODING_DEV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('a', 1),
+    ('b', 2),
+    ('c', 3),
+    ('d', 4),
+    ('e', 5),
+    ('f', 6),
+    ('g', 7),
+    ('h', 8),
+    ('i', 9),
+    ('j', 10),
+    ('k', 11),
+    ('l', 12),
+    ('m', 13),
+    ('n', 14),
+    ('o', 15),
+    ('p', 16),
+    ('q', 17),
+    ('r', 18),
+    ('s', 19),
+    ('t', 20),
+    ('u', 21),
+    ('v', 22),
+    ('w', 23),
+    ('x', 24),
+    ('y', 25),
+    ('z', 26),
+    ('ä', 27),
+    ('ö', 28),
+    ('ü', 29),
+    ('ß', 30),
+    ('0', 31),
+    ('1', 32),
+    ('2', 33),
+    ('3', 34),
+    ('4', 35),
+    ('5', 36),
+    ('6', 37),
+    ('7', 38),
+    ('8', 39),
+    ('9', 40),
+    ('.', 41),
+    (',', 42),
+    ('!', 43),
+    ('?', 44),
+    ('-', 45),
+    ('+', 46),
+    ('@', 47),
+], aliases: DASH_ALIASES);
+
+/// Encoding table for french alphabet
+const ENCODING_FRV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('a', 1),
+    ('b', 2),
+    ('c', 3),
+    ('d', 4),
+    ('e', 5),
+    ('f', 6),
+    ('g', 7),
+    ('h', 8),
+    ('i', 9),
+    ('j', 10),
+    ('k', 11),
+    ('l', 12),
+    ('m', 13),
+    ('n', 14),
+    ('o', 15),
+    ('p', 16),
+    ('q', 17),
+    ('r', 18),
+    ('s', 19),
+    ('t', 20),
+    ('u', 21),
+    ('v', 22),
+    ('w', 23),
+    ('x', 24),
+    ('y', 25),
+    ('z', 26),
+    ('à', 27),
+    ('â', 28),
+    ('æ', 29),
+    ('ç', 30),
+    ('é', 31),
+    ('è', 32),
+    ('ê', 33),
+    ('ë', 34),
+    ('î', 35),
+    ('ï', 36),
+    ('ô', 37),
+    ('œ', 38),
+    ('ù', 39),
+    ('û', 40),
+    ('ü', 41),
+    ('ÿ', 42),
+    ('0', 43),
+    ('1', 44),
+    ('2', 45),
+    ('3', 46),
+    ('4', 47),
+    ('5', 48),
+    ('6', 49),
+    ('7', 50),
+    ('8', 51),
+    ('9', 52),
+    ('.', 53),
+    (',', 54),
+    ('!', 55),
+    ('?', 56),
+    ('-', 57),
+    ('+', 58),
+    ('@', 59),
+    ('\'', 60),
+], aliases: DASH_ALIASES);
 
-#[derive(Debug, PartialEq)]
+/// Encoding table for spanish alphabet
+const ENCODING_ESV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('a', 1),
+    ('b', 2),
+    ('c', 3),
+    ('d', 4),
+    ('e', 5),
+    ('f', 6),
+    ('g', 7),
+    ('h', 8),
+    ('i', 9),
+    ('j', 10),
+    ('k', 11),
+    ('l', 12),
+    ('m', 13),
+    ('n', 14),
+    ('o', 15),
+    ('p', 16),
+    ('q', 17),
+    ('r', 18),
+    ('s', 19),
+    ('t', 20),
+    ('u', 21),
+    ('v', 22),
+    ('w', 23),
+    ('x', 24),
+    ('y', 25),
+    ('z', 26),
+    ('á', 27),
+    ('é', 28),
+    ('í', 29),
+    ('ñ', 30),
+    ('ó', 31),
+    ('ú', 32),
+    ('ü', 33),
+    ('0', 34),
+    ('1', 35),
+    ('2', 36),
+    ('3', 37),
+    ('4', 38),
+    ('5', 39),
+    ('6', 40),
+    ('7', 41),
+    ('8', 42),
+    ('9', 43),
+    ('.', 44),
+    (',', 45),
+    ('!', 46),
+    ('?', 47),
+    ('-', 48),
+    ('+', 49),
+    ('@', 50),
+    ('¿', 51),
+    ('¡', 52),
+], aliases: DASH_ALIASES);
+
+/// Encoding table for polish alphabet
+const ENCODING_PLV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('a', 1),
+    ('ą', 2),
+    ('b', 3),
+    ('c', 4),
+    ('ć', 5),
+    ('d', 6),
+    ('e', 7),
+    ('ę', 8),
+    ('f', 9),
+    ('g', 10),
+    ('h', 11),
+    ('i', 12),
+    ('j', 13),
+    ('k', 14),
+    ('l', 15),
+    ('ł', 16),
+    ('m', 17),
+    ('n', 18),
+    ('ń', 19),
+    ('o', 20),
+    ('ó', 21),
+    ('p', 22),
+    ('q', 23),
+    ('r', 24),
+    ('s', 25),
+    ('ś', 26),
+    ('t', 27),
+    ('u', 28),
+    ('v', 29),
+    ('w', 30),
+    ('x', 31),
+    ('y', 32),
+    ('z', 33),
+    ('ź', 34),
+    ('ż', 35),
+    ('0', 36),
+    ('1', 37),
+    ('2', 38),
+    ('3', 39),
+    ('4', 40),
+    ('5', 41),
+    ('6', 42),
+    ('7', 43),
+    ('8', 44),
+    ('9', 45),
+    ('.', 46),
+    (',', 47),
+    ('!', 48),
+    ('?', 49),
+    ('-', 50),
+    ('+', 51),
+    ('@', 52),
+], aliases: DASH_ALIASES);
+
+/// Encoding table for greek alphabet
+const ENCODING_ELV1: Encoding = encoding_table!([
+    (' ', 0),
+    ('α', 1),
+    ('β', 2),
+    ('γ', 3),
+    ('δ', 4),
+    ('ε', 5),
+    ('ζ', 6),
+    ('η', 7),
+    ('θ', 8),
+    ('ι', 9),
+    ('κ', 10),
+    ('λ', 11),
+    ('μ', 12),
+    ('ν', 13),
+    ('ξ', 14),
+    ('ο', 15),
+    ('π', 16),
+    ('ρ', 17),
+    ('σ', 18),
+    ('ς', 19),
+    ('τ', 20),
+    ('υ', 21),
+    ('φ', 22),
+    ('χ', 23),
+    ('ψ', 24),
+    ('ω', 25),
+    ('ά', 26),
+    ('έ', 27),
+    ('ή', 28),
+    ('ί', 29),
+    ('ό', 30),
+    ('ύ', 31),
+    ('ώ', 32),
+    ('ϊ', 33),
+    ('ϋ', 34),
+    ('0', 35),
+    ('1', 36),
+    ('2', 37),
+    ('3', 38),
+    ('4', 39),
+    ('5', 40),
+    ('6', 41),
+    ('7', 42),
+    ('8', 43),
+    ('9', 44),
+    ('.', 45),
+    (',', 46),
+    ('!', 47),
+    ('?', 48),
+    ('-', 49),
+    ('+', 50),
+    ('@', 51),
+    (';', 52),
+], aliases: DASH_ALIASES);
+
+/// Byte mode table: each char from U+0000 to U+00FF is encoded as its code point
+const ENCODING_BYTES: Encoding = encoding_table!(bytes_table());
+
+const fn bytes_table() -> [(char, u8); 256] {
+    let mut table = [('\0', 0); 256];
+
+    let mut i = 0;
+    while i < table.len() {
+        table[i] = (i as u8 as char, i as u8);
+        i += 1;
+    }
+
+    table
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Encoder {
     pub table: Encoding,
+    name: Option<String>,
     support_uppercase: bool,
-    pub size: u8,
+    normalization: Normalization,
+    transliteration: Transliteration,
+    grapheme_clusters: bool,
+    pub size: u16,
 }
 
 impl Encoder {
     /// Create encoder from predefined encoding tables
     pub fn new(encoding: EncodingType) -> Self {
-        match encoding {
-            EncodingType::RUv4 => Self {
-                table: ENCODING_RUV4,
-                size: ENCODING_RUV4.inner.len() as u8,
-                support_uppercase: false,
-            },
-            EncodingType::RUv5 => Self {
-                table: ENCODING_RUV5,
-                size: ENCODING_RUV5.inner.len() as u8,
-                support_uppercase: false,
-            },
-            EncodingType::ENv1 => Self {
-                table: ENCODING_ENV1,
-                size: ENCODING_ENV1.inner.len() as u8,
-                support_uppercase: false,
-            },
-            EncodingType::ENv2 => Self {
-                table: ENCODING_ENV2,
-                size: ENCODING_ENV2.inner.len() as u8,
-                support_uppercase: true,
-            },
-        }
+        let mut encoder = Self::from_table(encoding.encoding(), encoding.supports_uppercase());
+        encoder.name = Some(encoding.name().to_owned());
+        encoder
     }
 
     /// Load custom encoding table
@@ -308,51 +1536,548 @@ impl Encoder {
     /// let encoder = Encoder::load(MY_TABLE, false).unwrap();
     /// ```
     pub fn load(encoding: Encoding, support_uppercase: bool) -> Result<Self, CipherError> {
-        Ok(Self {
-            size: encoding.inner.len() as u8,
-            table: encoding,
-            support_uppercase,
-        })
+        let uses_separator = encoding.inner.iter().any(|&(c, _)| c == SYMBOL_SEPARATOR)
+            || encoding
+                .sequences
+                .iter()
+                .any(|(sequence, _)| sequence.contains(SYMBOL_SEPARATOR));
+        if !encoding.sequences.is_empty() && uses_separator {
+            return Err(CipherError::ReservedSeparator(SYMBOL_SEPARATOR));
+        }
+
+        Ok(Self::from_table(encoding, support_uppercase))
+    }
+
+    /// Returns true if encoder keeps uppercase chars, otherwise text is converted to lowercase
+    pub fn supports_uppercase(&self) -> bool {
+        self.support_uppercase
+    }
+
+    /// Chars of `text` that would be dropped by [`Encoder::encode`], without repeats.
+    /// Normalization and transliteration are taken into account
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    ///
+    /// let encoder = Encoder::new(EncodingType::ENv1);
+    /// assert_eq!(encoder.coverage("Café: 100%"), vec!['é', ':', '%']);
+    /// assert!(encoder.coverage("hello").is_empty());
+    /// ```
+    pub fn coverage(&self, text: &str) -> Vec<char> {
+        let mut dropped = Vec::new();
+        self.tokenize_with(text, |c| {
+            if !dropped.contains(&c) {
+                dropped.push(c);
+            }
+        });
+        dropped
+    }
+
+    /// Name of the encoding: built-in name like "ENv2" or the name it was registered with
+    /// in [`Registry`]. Encoders created with [`Encoder::load`] don't have a name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Rank built-in encodings by how many chars of `text` they can encode,
+    /// the best match goes first. See [`Registry::detect`] to include custom encodings
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::Encoder;
+    ///
+    /// // RUv4 is smaller, but has no digits
+    /// let detected = Encoder::detect("привет, мир 2!");
+    /// assert_eq!(detected[0].name, "RUv5");
+    /// assert!(detected[0].is_complete());
+    /// ```
+    pub fn detect(text: &str) -> Vec<Detection> {
+        Registry::new().detect(text)
+    }
+
+    /// Set normalization for chars that don't exist in the table.
+    /// By default, such chars are removed by encoder
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    /// use tinystorm::normalization::Normalization;
+    ///
+    /// let mut encoder = Encoder::new(EncodingType::ENv1);
+    /// encoder.set_normalization(Normalization::Decompose);
+    ///
+    /// let encoded = encoder.encode("Ça, straße");
+    /// assert_eq!(encoder.decode(&encoded), "ca, strasse");
+    /// ```
+    pub fn set_normalization(&mut self, normalization: Normalization) {
+        self.normalization = normalization;
+    }
+
+    /// Set transliteration for chars that don't exist in the table, so cyrillic text
+    /// can be encoded with latin tables and vice versa. Applied after normalization
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    /// use tinystorm::transliteration::Transliteration;
+    ///
+    /// let mut encoder = Encoder::new(EncodingType::ENv1);
+    /// encoder.set_transliteration(Transliteration::Gost);
+    /// let encoded = encoder.encode("жук");
+    /// assert_eq!(encoder.decode(&encoded), "zhuk");
+    ///
+    /// let mut encoder = Encoder::new(EncodingType::RUv5);
+    /// encoder.set_transliteration(Transliteration::Gost);
+    /// let encoded = encoder.encode("zhuk");
+    /// assert_eq!(encoder.decode(&encoded), "жук");
+    /// ```
+    pub fn set_transliteration(&mut self, transliteration: Transliteration) {
+        self.transliteration = transliteration;
+    }
+
+    /// Split text into grapheme clusters instead of chars, so emoji with modifiers and
+    /// ZWJ sequences like "👍🏽" or "👨‍👩‍👧" can be symbols of the table (as sequences).
+    /// Sequences are matched only as whole clusters, and unknown clusters are dropped
+    /// as a whole, instead of dropping some of their chars
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, Encoding};
+    /// use tinystorm::encoding_table;
+    ///
+    /// const CHAT: Encoding = encoding_table!(
+    ///     [(' ', 0), ('o', 1), ('k', 2), ('👍', 3)],
+    ///     sequences: [("👍🏽", 4), ("👨‍👩‍👧", 5)]
+    /// );
+    /// let mut encoder = Encoder::load(CHAT, false).unwrap();
+    /// encoder.set_grapheme_clusters(true);
+    ///
+    /// // "👍🏿" isn't in the table, so it is dropped instead of being encoded as "👍"
+    /// let encoded = encoder.encode("ok 👍🏽👨‍👩‍👧👍🏿👍");
+    /// assert_eq!(encoded, vec![1, 2, 0, 4, 5, 3]);
+    /// ```
+    pub fn set_grapheme_clusters(&mut self, enabled: bool) {
+        self.grapheme_clusters = enabled;
+    }
+
+    /// Normalize given str the same way [`Encoder::encode`] does,
+    /// returning the report of changed chars
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    /// use tinystorm::normalization::Normalization;
+    ///
+    /// let mut encoder = Encoder::new(EncodingType::ENv1);
+    /// encoder.set_normalization(Normalization::Decompose);
+    ///
+    /// let normalized = encoder.normalize("café");
+    /// assert_eq!(normalized.text, "cafe");
+    /// assert_eq!(normalized.changes[0].position, 3);
+    /// assert_eq!(normalized.changes[0].original, 'é');
+    /// ```
+    pub fn normalize(&self, str: &str) -> Normalized {
+        normalize(str, self.normalization, |c| self.knows_char(c))
     }
 
     /// Encode given str.
     /// If your encoder doesn't support uppercase,
     /// your chars will be converted to lowercase
     pub fn encode(&self, str: &str) -> Vec<u8> {
-        str.chars()
-            .filter_map(|c| {
-                self.encode_char(if !self.support_uppercase {
-                    c.to_lowercase().next().unwrap_or(c)
-                } else {
-                    c
-                })
-            })
-            .collect()
+        self.tokenize(str).into_iter().map(|(n, _)| n).collect()
     }
 
     /// Decode encoded bytes to string
     pub fn decode(&self, bytes: &[u8]) -> String {
-        bytes.iter().filter_map(|&c| self.decode_char(c)).collect()
+        let mut decoded = String::with_capacity(bytes.len());
+        for &n in bytes {
+            self.decode_symbol(n, false, &mut decoded);
+        }
+        decoded
+    }
+
+    /// Write encrypted symbols as ciphertext. Unlike [`Encoder::decode`], symbols are
+    /// separated with [`SYMBOL_SEPARATOR`] where they would be read back as a longer sequence
+    pub(crate) fn write_ciphertext(&self, symbols: &[u8]) -> String {
+        let written = symbols
+            .iter()
+            .map(|&n| {
+                let mut symbol = String::new();
+                self.decode_symbol(n, false, &mut symbol);
+                symbol.chars().collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let longest = self
+            .table
+            .sequences
+            .iter()
+            .map(|(sequence, _)| sequence.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut ciphertext = String::with_capacity(symbols.len());
+        for (i, symbol) in written.iter().enumerate() {
+            ciphertext.extend(symbol);
+
+            // Separators of the next symbols can only make sequences shorter,
+            // so it's enough to check the text without them
+            let rest = written[i..]
+                .iter()
+                .flatten()
+                .take(longest)
+                .copied()
+                .collect::<Vec<_>>();
+            let swallows_next = self
+                .match_sequence(&rest, |_| true)
+                .is_some_and(|(_, len)| len > symbol.len());
+            if swallows_next && i + 1 < written.len() {
+                ciphertext.push(SYMBOL_SEPARATOR);
+            }
+        }
+
+        ciphertext
+    }
+
+    /// Read symbols of ciphertext written with [`Encoder::write_ciphertext`]. Symbols are
+    /// matched exactly: case folding, normalization, transliteration and grapheme clusters
+    /// only apply to plaintext. Unknown chars are skipped
+    pub(crate) fn read_ciphertext(&self, ciphertext: &str) -> Vec<u8> {
+        let chars = ciphertext.chars().collect::<Vec<_>>();
+        let mut symbols = Vec::with_capacity(chars.len());
+
+        let mut i = 0;
+        while i < chars.len() {
+            if let Some((n, len)) = self.match_sequence(&chars[i..], |_| true) {
+                symbols.push(n);
+                i += len;
+                continue;
+            }
+
+            if let Some(n) = self.encode_char(chars[i]) {
+                symbols.push(n);
+            }
+            i += 1;
+        }
+
+        symbols
+    }
+
+    /// Encode given str and remember which of the encoded chars were uppercase.
+    /// Useful for encodings without uppercase support: the returned [`CaseMask`] can be
+    /// stored next to the ciphertext and applied back with [`Encoder::decode_with_case`]
+    /// # Example
+    /// ```
+    /// use tinystorm::encoding::{Encoder, EncodingType};
+    ///
+    /// let encoder = Encoder::new(EncodingType::ENv1);
+    /// let (encoded, mask) = encoder.encode_preserving_case("Hello");
+    ///
+    /// assert_eq!(encoder.decode(&encoded), "hello");
+    /// assert_eq!(encoder.decode_with_case(&encoded, &mask), "Hello");
+    /// ```
+    pub fn encode_preserving_case(&self, str: &str) -> (Vec<u8>, CaseMask) {
+        let mut mask = CaseMask::default();
+
+        let encoded = self
+            .tokenize(str)
+            .into_iter()
+            .map(|(n, upper)| {
+                mask.push(upper);
+                n
+            })
+            .collect();
+
+        (encoded, mask)
+    }
+
+    /// Decode encoded bytes to string and restore uppercase chars marked in `mask`.
+    /// Positions beyond the mask (for example, chunk padding) are left as is
+    pub fn decode_with_case(&self, bytes: &[u8], mask: &CaseMask) -> String {
+        let mut decoded = String::with_capacity(bytes.len());
+        for (i, &n) in bytes.iter().enumerate() {
+            self.decode_symbol(n, mask.is_upper(i), &mut decoded);
+        }
+        decoded
     }
 }
 
 // Private methods
 impl Encoder {
+    fn from_table(table: Encoding, support_uppercase: bool) -> Self {
+        Self {
+            size: table.size(),
+            table,
+            name: None,
+            support_uppercase,
+            normalization: Normalization::Off,
+            transliteration: Transliteration::Off,
+            grapheme_clusters: false,
+        }
+    }
+
     // Helper functions
-    fn encode_char(&self, c: char) -> Option<u8> {
-        self.table
-            .inner
+    fn fold_case(&self, c: char) -> char {
+        if !self.support_uppercase {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    }
+
+    /// Split str into symbols of the table using greedy longest match.
+    /// Returns encoded symbols with flag whether the first char of the symbol was uppercase.
+    /// Chars that don't exist in the table are skipped
+    fn tokenize(&self, str: &str) -> Vec<(u8, bool)> {
+        self.tokenize_with(str, |_| {})
+    }
+
+    /// Same as [`Encoder::tokenize`], but calls `on_dropped` for each skipped char
+    fn tokenize_with(&self, str: &str, mut on_dropped: impl FnMut(char)) -> Vec<(u8, bool)> {
+        let normalized = match self.normalization {
+            Normalization::Off => Cow::Borrowed(str),
+            _ => Cow::Owned(self.normalize(str).text),
+        };
+        let transliterated = match self.transliteration {
+            Transliteration::Off => normalized,
+            _ => Cow::Owned(transliterate(&normalized, self.transliteration, |c| {
+                self.knows_char(c)
+            })),
+        };
+
+        let original = transliterated.chars().collect::<Vec<_>>();
+        let folded = original
             .iter()
-            .find(|&&(ch, _)| ch == c)
-            .map(|&(_, n)| n)
+            .map(|&c| self.fold_case(c))
+            .collect::<Vec<_>>();
+
+        // Without grapheme clusters, every char is a cluster of its own
+        let cluster_lengths = if self.grapheme_clusters {
+            cluster_lengths(&folded)
+        } else {
+            vec![1; folded.len()]
+        };
+        let mut is_boundary = vec![false; folded.len() + 1];
+        let mut cluster_len_at = vec![0; folded.len()];
+        let mut position = 0;
+        for len in cluster_lengths {
+            is_boundary[position] = true;
+            cluster_len_at[position] = len;
+            position += len;
+        }
+        is_boundary[position] = true;
+
+        let mut tokens = Vec::with_capacity(folded.len());
+        let mut i = 0;
+        while i < folded.len() {
+            // If folding changed the char, it was uppercase
+            let upper = folded[i] != original[i];
+
+            // Sequence must consist of whole clusters
+            if let Some((n, len)) = self.match_sequence(&folded[i..], |len| is_boundary[i + len]) {
+                tokens.push((n, upper));
+                i += len;
+                continue;
+            }
+
+            let len = cluster_len_at[i];
+            let is_line_break = folded[i..i + len]
+                .iter()
+                .all(|&c| c.is_whitespace() || c.is_control());
+            if len > 1 && is_line_break {
+                // Clusters like "\r\n" keep their known chars
+                for j in i..i + len {
+                    match self.encode_char(folded[j]) {
+                        Some(n) => tokens.push((n, false)),
+                        None => on_dropped(original[j]),
+                    }
+                }
+                i += len;
+                continue;
+            }
+
+            // Unknown cluster is dropped as a whole
+            match self.encode_char(folded[i]).filter(|_| len == 1) {
+                Some(n) => tokens.push((n, upper)),
+                None => original[i..i + len].iter().for_each(|&c| on_dropped(c)),
+            }
+            i += len;
+        }
+
+        tokens
     }
 
-    fn decode_char(&self, n: u8) -> Option<char> {
+    /// Find the longest sequence at the start of `chars` which ends where `ends_at` allows.
+    /// Returns its encoded number and length in chars
+    fn match_sequence(
+        &self,
+        chars: &[char],
+        ends_at: impl Fn(usize) -> bool,
+    ) -> Option<(u8, usize)> {
         self.table
-            .inner
+            .sequences
+            .iter()
+            .filter_map(|&(sequence, n)| {
+                let len = sequence.chars().count();
+                let matches = chars.len() >= len
+                    && ends_at(len)
+                    && sequence.chars().eq(chars[..len].iter().copied());
+                matches.then_some((n, len))
+            })
+            .max_by_key(|&(_, len)| len)
+    }
+
+    /// Returns true if char is a part of any symbol of the table (considering case folding),
+    /// so normalization shouldn't touch it
+    fn knows_char(&self, c: char) -> bool {
+        let c = self.fold_case(c);
+        self.encode_char(c).is_some()
+            || self
+                .table
+                .sequences
+                .iter()
+                .any(|&(sequence, _)| sequence.contains(c))
+    }
+
+    fn encode_char(&self, c: char) -> Option<u8> {
+        self.table.code_of(c)
+    }
+
+    /// Push decoded symbol to `buffer`. If `upper` is true, the first char is made uppercase
+    fn decode_symbol(&self, n: u8, upper: bool, buffer: &mut String) {
+        let mut push = |c: char| {
+            if upper {
+                buffer.extend(c.to_uppercase());
+            } else {
+                buffer.push(c);
+            }
+        };
+
+        if let Some(c) = self.table.char_of(n) {
+            push(c);
+        } else if let Some(sequence) = self.table.sequence_of(n) {
+            let mut chars = sequence.chars();
+            if let Some(first) = chars.next() {
+                push(first);
+            }
+            buffer.extend(chars);
+        }
+    }
+}
+
+/// Result of encoding detection, see [`Encoder::detect`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// Name of the encoding, can be resolved with [`Registry::get`]
+    pub name: String,
+    /// Amount of chars that can be encoded
+    pub covered: usize,
+    /// Amount of chars in the text
+    pub total: usize,
+}
+
+impl Detection {
+    /// Returns true if encoding covers all chars of the text
+    pub fn is_complete(&self) -> bool {
+        self.covered == self.total
+    }
+}
+
+/// Named encodings: all built-in encodings plus registered custom ones
+/// # Example
+/// ```
+/// use tinystorm::encoding::{Encoding, Registry};
+/// use tinystorm::encoding_table;
+///
+/// const MY_TABLE: Encoding = encoding_table!([(' ', 0), ('a', 1), ('b', 2), ('c', 3)]);
+///
+/// let mut registry = Registry::new();
+/// registry.register("abc", MY_TABLE, false).unwrap();
+///
+/// assert_eq!(registry.detect("abc cab")[0].name, "abc");
+/// assert_eq!(registry.get("abc").unwrap().size, 4);
+/// assert_eq!(registry.get("ENv2").unwrap().size, 70);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Registry {
+    custom: Vec<Encoder>,
+}
+
+impl Registry {
+    /// Create registry with built-in encodings only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register custom encoding under `name`. Name can consist of ASCII letters, digits,
+    /// '-' and '_' (it is written in ciphertext headers), and must not be taken by built-in
+    /// or already registered encoding
+    pub fn register(
+        &mut self,
+        name: &str,
+        encoding: Encoding,
+        support_uppercase: bool,
+    ) -> Result<(), CipherError> {
+        if !is_valid_name(name) {
+            return Err(CipherError::InvalidEncodingName(name.to_owned()));
+        }
+        if self.get(name).is_ok() {
+            return Err(CipherError::EncodingAlreadyRegistered(name.to_owned()));
+        }
+
+        let mut encoder = Encoder::load(encoding, support_uppercase)?;
+        encoder.name = Some(name.to_owned());
+        self.custom.push(encoder);
+
+        Ok(())
+    }
+
+    /// Find encoding by name. Custom encodings are looked up by exact name,
+    /// built-in ones as in [`EncodingType::from_str`]
+    pub fn get(&self, name: &str) -> Result<Encoder, CipherError> {
+        if let Some(encoder) = self.custom.iter().find(|e| e.name() == Some(name)) {
+            return Ok(encoder.clone());
+        }
+
+        name.parse::<EncodingType>().map(Encoder::new)
+    }
+
+    /// Names of all encodings, built-in ones go first
+    pub fn list(&self) -> Vec<String> {
+        EncodingType::list()
             .iter()
-            .find(|&&(_, num)| num == n)
-            .map(|&(ch, _)| ch)
+            .map(|encoding| encoding.name().to_owned())
+            .chain(self.custom.iter().filter_map(|e| e.name.clone()))
+            .collect()
+    }
+
+    /// Rank all encodings by how many chars of `text` they can encode, the best match goes first.
+    /// If several encodings cover the same amount of chars, encodings with uppercase support
+    /// are preferred for text with uppercase chars, and then smaller encodings
+    pub fn detect(&self, text: &str) -> Vec<Detection> {
+        let has_uppercase = text.chars().any(char::is_uppercase);
+        let total = text.chars().count();
+
+        let built_in = EncodingType::list().iter().map(|&e| Encoder::new(e));
+        let mut ranked = built_in
+            .chain(self.custom.iter().cloned())
+            .map(|encoder| {
+                let covered = text.chars().filter(|&c| encoder.knows_char(c)).count();
+                (covered, encoder)
+            })
+            .collect::<Vec<_>>();
+
+        ranked.sort_by_key(|(covered, encoder)| {
+            (
+                Reverse(*covered),
+                !(has_uppercase && encoder.support_uppercase),
+                encoder.size,
+            )
+        });
+
+        ranked
+            .into_iter()
+            .map(|(covered, encoder)| Detection {
+                name: encoder.name.unwrap_or_default(),
+                covered,
+                total,
+            })
+            .collect()
     }
 }
 
@@ -378,3 +2103,217 @@ pub const fn check_for_malformed_encoding(data: &[(char, u8)]) -> bool {
 
     true
 }
+
+#[doc(hidden)]
+/// Amount of chars in str, usable in const context
+pub const fn char_count(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        // Continuation bytes of UTF-8 look like 0b10xxxxxx
+        if bytes[i] & 0b1100_0000 != 0b1000_0000 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+#[doc(hidden)]
+/// Build table from chars of str, numbering them in order. `N` must be equal to
+/// [`char_count`] of the str. Used by [`encoding_from_str`] macro
+///
+/// [`encoding_from_str`]: crate::encoding_from_str
+pub const fn table_from_str<const N: usize>(s: &str) -> [(char, u8); N] {
+    let bytes = s.as_bytes();
+    let mut table = [('\0', 0); N];
+
+    let mut i = 0;
+    let mut n = 0;
+    while i < bytes.len() {
+        // Decode UTF-8 manually, since str::chars isn't const
+        let first = bytes[i] as u32;
+        let (mut code, len) = match first {
+            0x00..=0x7f => (first, 1),
+            0xc0..=0xdf => (first & 0x1f, 2),
+            0xe0..=0xef => (first & 0x0f, 3),
+            _ => (first & 0x07, 4),
+        };
+
+        let mut j = 1;
+        while j < len {
+            code = (code << 6) | (bytes[i + j] as u32 & 0x3f);
+            j += 1;
+        }
+
+        table[n] = match char::from_u32(code) {
+            Some(c) => (c, n as u8),
+            None => panic!("Invalid UTF-8"),
+        };
+        n += 1;
+        i += len;
+    }
+
+    table
+}
+
+/// Compile-time check for custom encoding sequences. Returns true if sequences are correct:
+/// each sequence has at least 2 chars, is unique and its number isn't used by other symbols
+pub const fn check_for_malformed_sequences(data: &[(char, u8)], sequences: &[(&str, u8)]) -> bool {
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    let len = sequences.len();
+
+    let mut i = 0;
+    while i < len {
+        let (sequence, n) = sequences[i];
+        if char_count(sequence) < 2 {
+            return false;
+        }
+
+        let mut j = 0;
+        while j < data.len() {
+            if data[j].1 == n {
+                return false;
+            }
+            j += 1;
+        }
+
+        let mut next = i + 1;
+        while next < len {
+            if str_eq(sequence, sequences[next].0) || n == sequences[next].1 {
+                return false;
+            }
+            next += 1;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Compile-time check for custom encoding aliases. Returns true if aliases are correct:
+/// each alias is unique, doesn't shadow a char of the table and points to a char that exists
+/// in the table
+pub const fn check_for_malformed_aliases(data: &[(char, u8)], aliases: &[(char, char)]) -> bool {
+    const fn contains(data: &[(char, u8)], c: char) -> bool {
+        let mut i = 0;
+        while i < data.len() {
+            if data[i].0 == c {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    let len = aliases.len();
+
+    let mut i = 0;
+    while i < len {
+        let (alias, target) = aliases[i];
+        if contains(data, alias) || !contains(data, target) {
+            return false;
+        }
+
+        // The same alias can't point to different chars
+        let mut next = i + 1;
+        while next < len {
+            if alias == aliases[next].0 {
+                return false;
+            }
+            next += 1;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Positions of uppercase chars in a message encoded with [`Encoder::encode_preserving_case`].
+/// Displayed as hex string where each digit covers 4 chars (the first char is the highest bit),
+/// so it can be carried next to the ciphertext as plain text
+/// # Example
+/// ```
+/// use tinystorm::encoding::CaseMask;
+///
+/// let mask: CaseMask = "88".parse().unwrap();
+/// assert!(mask.is_upper(0));
+/// assert!(mask.is_upper(4));
+/// assert!(!mask.is_upper(1));
+/// assert_eq!(mask.to_string(), "88");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaseMask {
+    bits: Vec<bool>,
+}
+
+impl CaseMask {
+    /// Returns true if char at `position` should be uppercase
+    pub fn is_upper(&self, position: usize) -> bool {
+        self.bits.get(position).copied().unwrap_or(false)
+    }
+
+    /// Returns true if mask has at least one uppercase position
+    pub fn has_uppercase(&self) -> bool {
+        self.bits.contains(&true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, upper: bool) {
+        self.bits.push(upper);
+    }
+}
+
+impl Display for CaseMask {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for nibble in self.bits.chunks(4) {
+            let digit = nibble
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &upper)| acc | ((upper as u32) << (3 - i)));
+            // Digit is always less than 16
+            write!(f, "{digit:x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CaseMask {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bits = Vec::with_capacity(s.len() * 4);
+
+        for c in s.trim().chars() {
+            let digit = c.to_digit(16).ok_or(CipherError::MalformedCaseMask)?;
+            for i in (0..4).rev() {
+                bits.push(digit & (1 << i) != 0);
+            }
+        }
+
+        Ok(Self { bits })
+    }
+}
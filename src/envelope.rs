@@ -0,0 +1,99 @@
+use std::fmt::Display;
+
+/// Metadata written in front of the ciphertext as a header:
+/// `[kid=team;enc=ENv2;fp=1a2b3c4d]ciphertext`.
+/// Fields are separated by ';', unknown fields are ignored. Header must have at least one
+/// known field, since tables with brackets can produce ciphertexts like `[]nL}J)F`
+/// # Example
+/// ```
+/// use tinystorm::envelope::Envelope;
+///
+/// let (envelope, body) = Envelope::split("[enc=ENv2]gzZQ8izmBOEw");
+/// assert_eq!(envelope.unwrap().encoding.as_deref(), Some("ENv2"));
+/// assert_eq!(body, "gzZQ8izmBOEw");
+///
+/// // Text without header is returned as is
+/// let (envelope, body) = Envelope::split("gzZQ8izmBOEw");
+/// assert_eq!(envelope, None);
+/// assert_eq!(body, "gzZQ8izmBOEw");
+///
+/// // Brackets without known fields are the part of the ciphertext
+/// assert_eq!(Envelope::split("[]nL}J)F"), (None, "[]nL}J)F"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Envelope {
+    /// Key ID in the keyring, see [`Keyring`](crate::keyring::Keyring)
+    pub key_id: Option<String>,
+    /// Name of the encoding used for the ciphertext
+    pub encoding: Option<String>,
+    /// Fingerprint of the encoding table, see [`Encoding::fingerprint`]
+    ///
+    /// [`Encoding::fingerprint`]: crate::encoding::Encoding::fingerprint
+    pub fingerprint: Option<u32>,
+}
+
+/// Check that name can be a value of header field: key ID or encoding name.
+/// Name can consist of ASCII letters, digits, '-' and '_'
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Envelope {
+    /// Split text into header and ciphertext. Returns `None` if text doesn't have a valid header
+    pub fn split(text: &str) -> (Option<Envelope>, &str) {
+        let parse = || {
+            let rest = text.strip_prefix('[')?;
+            let (header, body) = rest.split_once(']')?;
+
+            let mut envelope = Envelope::default();
+            for field in header.split(';').filter(|field| !field.is_empty()) {
+                let (key, value) = field.split_once('=')?;
+                if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return None;
+                }
+
+                match key {
+                    "kid" => envelope.key_id = Some(value.to_owned()),
+                    "enc" => envelope.encoding = Some(value.to_owned()),
+                    "fp" => envelope.fingerprint = Some(u32::from_str_radix(value, 16).ok()?),
+                    _ => {}
+                }
+            }
+
+            if envelope == Envelope::default() {
+                return None;
+            }
+            Some((envelope, body))
+        };
+
+        match parse() {
+            Some((envelope, body)) => (Some(envelope), body),
+            None => (None, text),
+        }
+    }
+
+    /// Put header in front of `body`
+    pub fn wrap(&self, body: &str) -> String {
+        format!("{self}{body}")
+    }
+}
+
+impl Display for Envelope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut fields = Vec::new();
+        if let Some(key_id) = &self.key_id {
+            fields.push(format!("kid={key_id}"));
+        }
+        if let Some(encoding) = &self.encoding {
+            fields.push(format!("enc={encoding}"));
+        }
+        if let Some(fingerprint) = self.fingerprint {
+            fields.push(format!("fp={fingerprint:08x}"));
+        }
+
+        write!(f, "[{}]", fields.join(";"))
+    }
+}
@@ -7,8 +7,31 @@ pub enum CipherError {
     InvalidIV,
     ParseIntError(std::num::ParseIntError),
     MalformedEncoding,
-    IVOutOfBounds(u8),
-    KeyOutOfBounds(u8, u8),
+    IVOutOfBounds(u16),
+    KeyOutOfBounds(u8, u16),
+    MalformedCaseMask,
+    UnknownEncoding(String),
+    EncodingAlreadyRegistered(String),
+    InvalidEncodingName(String),
+    ReservedSeparator(char),
+    InvalidPadding,
+    EncodingMismatch,
+    MissingFingerprint,
+    InvalidEncodingSize(u16),
+    MalformedCredentials(usize),
+    CheckDigitMismatch(usize),
+    UnknownKeyId(String),
+    MissingKeyId,
+    DuplicateKeyId(String),
+    InvalidKeyId(String),
+    MalformedKeyring(usize),
+    Io(std::io::ErrorKind),
+    UntranslatableSymbol(u8),
+    UnknownWord(String),
+    AmbiguousWord(String),
+    InvalidChecksum,
+    InvalidGroup,
+    InvalidExchangeKey(u64),
 }
 
 impl Display for CipherError {
@@ -24,6 +47,74 @@ impl Display for CipherError {
             CipherError::KeyOutOfBounds(chunk, bounds) => {
                 write!(f, "Key chunk {chunk} out of bounds: {bounds}")
             }
+            CipherError::MalformedCaseMask => {
+                write!(f, "Malformed case mask: expected hex digits")
+            }
+            CipherError::UnknownEncoding(name) => write!(f, "Unknown encoding: {name}"),
+            CipherError::EncodingAlreadyRegistered(name) => {
+                write!(f, "Encoding {name} is already registered")
+            }
+            CipherError::InvalidEncodingName(name) => {
+                write!(
+                    f,
+                    "Invalid encoding name {name}: use ASCII letters, digits, '-' and '_'"
+                )
+            }
+            CipherError::ReservedSeparator(c) => {
+                write!(
+                    f,
+                    "Tables with sequences can't have '{c}', it separates symbols in ciphertexts"
+                )
+            }
+            CipherError::InvalidPadding => write!(f, "Invalid padding of the encrypted data"),
+            CipherError::EncodingMismatch => {
+                write!(f, "Ciphertext was encrypted with a different encoding")
+            }
+            CipherError::MissingFingerprint => {
+                write!(f, "Ciphertext doesn't have encoding fingerprint")
+            }
+            CipherError::InvalidEncodingSize(size) => {
+                write!(f, "Encoding size {size} must be within 1..=256")
+            }
+            CipherError::MalformedCredentials(position) => {
+                write!(f, "Malformed credentials at position {position}")
+            }
+            CipherError::CheckDigitMismatch(position) => {
+                write!(
+                    f,
+                    "Check digit at position {position} doesn't match, check for typos"
+                )
+            }
+            CipherError::UnknownKeyId(id) => write!(f, "Unknown key ID: {id}"),
+            CipherError::MissingKeyId => write!(f, "Ciphertext doesn't have key ID"),
+            CipherError::DuplicateKeyId(id) => write!(f, "Key ID {id} is already in the keyring"),
+            CipherError::InvalidKeyId(id) => {
+                write!(
+                    f,
+                    "Invalid key ID {id}: use ASCII letters, digits, '-' and '_'"
+                )
+            }
+            CipherError::MalformedKeyring(line) => write!(f, "Malformed keyring at line {line}"),
+            CipherError::Io(kind) => write!(f, "I/O error: {kind}"),
+            CipherError::UntranslatableSymbol(code) => {
+                write!(f, "Symbol {code} doesn't exist in the new encoding")
+            }
+            CipherError::UnknownWord(word) => write!(f, "Unknown word: {word}"),
+            CipherError::AmbiguousWord(word) => {
+                write!(f, "Ambiguous word: {word}, write more letters")
+            }
+            CipherError::InvalidGroup => {
+                write!(
+                    f,
+                    "Invalid group: modulus must be a prime and generator below it"
+                )
+            }
+            CipherError::InvalidExchangeKey(n) => {
+                write!(f, "Exchange number {n} must be within 2..prime - 1")
+            }
+            CipherError::InvalidChecksum => {
+                write!(f, "Checksum word doesn't match, check for typos")
+            }
         }
     }
 }
@@ -0,0 +1,243 @@
+//! Toy Diffie-Hellman key agreement for classroom exercises: two parties agree on
+//! credentials over an open channel. Numbers are small enough to compute by hand,
+//! so it is not secure in any way
+
+use crate::error::CipherError;
+use crate::key::Credentials;
+use crate::random::{OsRandom, RandomSource};
+use std::fmt::Display;
+
+/// Salt of the passphrase derivation which turns shared secret into credentials
+const EXCHANGE_SALT: &str = "tinystorm-exchange";
+
+/// Public parameters: prime modulus and generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Group {
+    prime: u64,
+    generator: u64,
+}
+
+impl Group {
+    /// Textbook example, p = 23 and g = 5
+    pub const TEXTBOOK: Group = Group {
+        prime: 23,
+        generator: 5,
+    };
+    /// Still computable by hand with some patience, p = 227 and g = 2
+    pub const CLASSROOM: Group = Group {
+        prime: 227,
+        generator: 2,
+    };
+
+    /// Create a group, `prime` must be a prime up to 2^32 and `generator` within 2..prime - 1
+    pub fn new(prime: u64, generator: u64) -> Result<Self, CipherError> {
+        let is_prime = prime >= 5
+            && prime <= u32::MAX as u64
+            && (2..)
+                .take_while(|d| d * d <= prime)
+                .all(|d| !prime.is_multiple_of(d));
+        if !is_prime || !(2..prime - 1).contains(&generator) {
+            return Err(CipherError::InvalidGroup);
+        }
+
+        Ok(Group { prime, generator })
+    }
+
+    pub fn prime(&self) -> u64 {
+        self.prime
+    }
+
+    pub fn generator(&self) -> u64 {
+        self.generator
+    }
+
+    /// `base` to the power of `exponent` modulo the prime
+    pub fn pow(&self, base: u64, exponent: u64) -> u64 {
+        let modulus = self.prime as u128;
+        let (mut base, mut exponent, mut result) = (base as u128 % modulus, exponent, 1u128);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base % modulus;
+            }
+            base = base * base % modulus;
+            exponent >>= 1;
+        }
+        result as u64
+    }
+
+    /// Check that a secret or a public number is within 2..prime - 1. 1 and prime - 1
+    /// are excluded, since powers of them are 1 or prime - 1
+    fn verify(&self, n: u64) -> Result<u64, CipherError> {
+        if (2..self.prime - 1).contains(&n) {
+            Ok(n)
+        } else {
+            Err(CipherError::InvalidExchangeKey(n))
+        }
+    }
+}
+
+/// One side of the exchange with its secret number
+#[derive(Clone, PartialEq)]
+pub struct Party {
+    group: Group,
+    secret: u64,
+}
+
+impl std::fmt::Debug for Party {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Party")
+            .field("group", &self.group)
+            .field("secret", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl Party {
+    /// Party with the secret picked by hand. Secret must be within 2..prime - 1,
+    /// and its public key too
+    pub fn new(group: Group, secret: u64) -> Result<Self, CipherError> {
+        let party = Party {
+            group,
+            secret: group.verify(secret)?,
+        };
+        group
+            .verify(party.public_key())
+            .map_err(|_| CipherError::InvalidExchangeKey(secret))?;
+
+        Ok(party)
+    }
+
+    /// Party with a random secret from the entropy source of the OS
+    pub fn generate(group: Group) -> Self {
+        loop {
+            let secret = 2 + OsRandom.below(group.prime - 3);
+            if let Ok(party) = Party::new(group, secret) {
+                return party;
+            }
+        }
+    }
+
+    /// Number to send to the other party: generator to the power of the secret
+    pub fn public_key(&self) -> u64 {
+        self.group.pow(self.group.generator, self.secret)
+    }
+
+    /// Shared secret from the public key of the other party
+    pub fn shared_secret(&self, other_public: u64) -> Result<u64, CipherError> {
+        let other_public = self.group.verify(other_public)?;
+        Ok(self.group.pow(other_public, self.secret))
+    }
+
+    /// Credentials from the public key of the other party, which fit the encoding
+    /// of given size. Both parties get the same credentials
+    /// # Example
+    /// ```
+    /// use tinystorm::exchange::{Group, Party};
+    ///
+    /// let alice = Party::generate(Group::CLASSROOM);
+    /// let bob = Party::generate(Group::CLASSROOM);
+    ///
+    /// // Only public keys are sent over the channel
+    /// let (a, b) = (alice.public_key(), bob.public_key());
+    /// assert_eq!(alice.credentials(b, 42), bob.credentials(a, 42));
+    /// ```
+    pub fn credentials(
+        &self,
+        other_public: u64,
+        encoding_size: u16,
+    ) -> Result<Credentials, CipherError> {
+        derive_credentials(self.shared_secret(other_public)?, encoding_size)
+    }
+}
+
+/// Map shared secret to credentials with [`Credentials::from_passphrase`]
+pub fn derive_credentials(
+    shared_secret: u64,
+    encoding_size: u16,
+) -> Result<Credentials, CipherError> {
+    Credentials::from_passphrase(&shared_secret.to_string(), EXCHANGE_SALT, encoding_size)
+}
+
+/// All steps of the exchange between Alice and Bob, printable for the class
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript {
+    pub group: Group,
+    pub alice_secret: u64,
+    pub bob_secret: u64,
+    pub alice_public: u64,
+    pub bob_public: u64,
+    pub shared_secret: u64,
+    pub credentials: Credentials,
+    pub encoding_size: u16,
+}
+
+impl Transcript {
+    /// Run the exchange with secrets picked by hand
+    /// # Example
+    /// ```
+    /// use tinystorm::exchange::{Group, Transcript};
+    ///
+    /// let transcript = Transcript::run(Group::TEXTBOOK, 4, 3, 42).unwrap();
+    /// assert_eq!(transcript.shared_secret, 18);
+    /// println!("{transcript}");
+    /// ```
+    pub fn run(
+        group: Group,
+        alice_secret: u64,
+        bob_secret: u64,
+        encoding_size: u16,
+    ) -> Result<Self, CipherError> {
+        let alice = Party::new(group, alice_secret)?;
+        let bob = Party::new(group, bob_secret)?;
+        let (alice_public, bob_public) = (alice.public_key(), bob.public_key());
+
+        let shared_secret = alice.shared_secret(bob_public)?;
+        debug_assert_eq!(Ok(shared_secret), bob.shared_secret(alice_public));
+
+        Ok(Transcript {
+            group,
+            alice_secret,
+            bob_secret,
+            alice_public,
+            bob_public,
+            shared_secret,
+            credentials: derive_credentials(shared_secret, encoding_size)?,
+            encoding_size,
+        })
+    }
+}
+
+impl Display for Transcript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let Group {
+            prime: p,
+            generator: g,
+        } = self.group;
+        let (a, b) = (self.alice_secret, self.bob_secret);
+        let (big_a, big_b) = (self.alice_public, self.bob_public);
+        let s = self.shared_secret;
+
+        writeln!(f, "1. Alice and Bob agree on public p = {p} and g = {g}")?;
+        writeln!(
+            f,
+            "2. Alice picks secret a = {a} and sends A = g^a mod p = {g}^{a} mod {p} = {big_a}"
+        )?;
+        writeln!(
+            f,
+            "3. Bob picks secret b = {b} and sends B = g^b mod p = {g}^{b} mod {p} = {big_b}"
+        )?;
+        writeln!(
+            f,
+            "4. Alice computes s = B^a mod p = {big_b}^{a} mod {p} = {s}"
+        )?;
+        writeln!(
+            f,
+            "5. Bob computes s = A^b mod p = {big_a}^{b} mod {p} = {s}"
+        )?;
+        write!(
+            f,
+            "6. Both derive credentials for encoding size {} from s: {}",
+            self.encoding_size, self.credentials
+        )
+    }
+}
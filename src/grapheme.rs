@@ -0,0 +1,91 @@
+//! Simplified segmentation of text into grapheme clusters (user-perceived characters).
+//! Handles what matters for chat messages: combining marks, variation selectors,
+//! emoji modifiers, ZWJ sequences, tag sequences, flags and CRLF
+
+use crate::normalization::is_combining_mark;
+
+const ZWJ: char = '\u{200d}';
+
+/// Returns true if `c` is attached to the previous char
+fn is_extend(c: char) -> bool {
+    is_combining_mark(c)
+        || c == ZWJ
+        || matches!(
+            c,
+            // Variation selectors
+            '\u{fe00}'..='\u{fe0f}'
+                | '\u{e0100}'..='\u{e01ef}'
+                // Emoji skin tone modifiers
+                | '\u{1f3fb}'..='\u{1f3ff}'
+                // Tags, used in subdivision flags
+                | '\u{e0020}'..='\u{e007f}'
+        )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1f1e6}'..='\u{1f1ff}')
+}
+
+/// Lengths (in chars) of grapheme clusters of `chars`
+pub(crate) fn cluster_lengths(chars: &[char]) -> Vec<usize> {
+    let mut lengths = Vec::with_capacity(chars.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let first = chars[i];
+        i += 1;
+
+        if first == '\r' && chars.get(i) == Some(&'\n') {
+            i += 1;
+        } else if is_regional_indicator(first)
+            && chars.get(i).copied().is_some_and(is_regional_indicator)
+        {
+            // Flag is a pair of regional indicators
+            i += 1;
+        }
+
+        while i < chars.len() {
+            if is_extend(chars[i]) {
+                i += 1;
+            } else if chars[i - 1] == ZWJ && !chars[i].is_whitespace() {
+                // ZWJ glues the next char, like in "👨‍👩‍👧"
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        lengths.push(i - start);
+    }
+
+    lengths
+}
+
+/// Split text into grapheme clusters
+/// # Example
+/// ```
+/// use tinystorm::grapheme::clusters;
+///
+/// assert_eq!(clusters("a👍🏽e\u{301}"), vec!["a", "👍🏽", "e\u{301}"]);
+/// assert_eq!(clusters("👨‍👩‍👧!"), vec!["👨‍👩‍👧", "!"]);
+/// ```
+pub fn clusters(text: &str) -> Vec<&str> {
+    let chars = text.chars().collect::<Vec<_>>();
+
+    let mut result = Vec::new();
+    let mut offset = 0;
+    let mut position = 0;
+    for len in cluster_lengths(&chars) {
+        let bytes = chars[position..position + len]
+            .iter()
+            .map(|c| c.len_utf8())
+            .sum::<usize>();
+
+        result.push(&text[offset..offset + bytes]);
+        offset += bytes;
+        position += len;
+    }
+
+    result
+}
@@ -1,5 +1,11 @@
-use crate::cipher::CHUNK_SIZE;
+use crate::cipher::{BYTE_MODE_SIZE, CHUNK_SIZE, add_mod};
 use crate::error::CipherError;
+use crate::random::{OsRandom, RandomSource};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Work factor of [`Credentials::from_passphrase`]
+pub const DEFAULT_KDF_ROUNDS: u32 = 10_000;
 
 /// Check if the new key has the correct form.
 /// Returns true if form is correct
@@ -11,13 +17,51 @@ pub(crate) fn verify_key(key: &[u8]) -> bool {
     true
 }
 
-/// Credentials of the cipher: Key and IV
-#[derive(Debug, PartialEq)]
+/// Credentials of the cipher: Key and IV.
+///
+/// Credentials can be written as one canonical string, like "25-21-08-40/39-0": key parts,
+/// IV and a check digit which catches typos (Luhn algorithm over the digits of key and IV).
+/// Parts are written with 2 digits, or with 3 digits each if any of them is above 99.
+/// Parser also accepts whitespaces instead of dashes and key without separators
+/// # Example
+/// ```
+/// use tinystorm::error::CipherError;
+/// use tinystorm::key::Credentials;
+///
+/// let credentials = Credentials::new(&[25, 21, 8, 40], 39);
+/// assert_eq!(credentials.to_string(), "25-21-08-40/39-0");
+///
+/// assert_eq!("25210840 / 39 0".parse(), Ok(credentials));
+/// // Typo in the IV
+/// assert_eq!(
+///     "25-21-08-40/38-0".parse::<Credentials>(),
+///     Err(CipherError::CheckDigitMismatch(15))
+/// );
+/// ```
+///
+/// Debug output doesn't show the key, and the key is wiped from memory on drop
+#[derive(Clone, PartialEq)]
 pub struct Credentials {
     pub(crate) key: Vec<u8>,
     pub(crate) iv: u8,
 }
 
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("key", &format_args!("<redacted>"))
+            .field("iv", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+impl Drop for Credentials {
+    fn drop(&mut self) {
+        wipe(&mut self.key);
+        wipe(std::slice::from_mut(&mut self.iv));
+    }
+}
+
 impl Credentials {
     pub fn new(key: &[u8], iv: u8) -> Self {
         Self {
@@ -26,14 +70,150 @@ impl Credentials {
         }
     }
 
+    /// Key parts and IV. Debug output of credentials is redacted, so use it
+    /// when you really need to see the key
+    /// # Example
+    /// ```
+    /// use tinystorm::key::Credentials;
+    ///
+    /// let credentials = Credentials::new(&[25, 21, 8, 40], 39);
+    /// assert_eq!(
+    ///     format!("{credentials:?}"),
+    ///     "Credentials { key: <redacted>, iv: <redacted> }"
+    /// );
+    /// assert_eq!(credentials.expose(), (&[25, 21, 8, 40][..], 39));
+    /// ```
+    pub fn expose(&self) -> (&[u8], u8) {
+        (&self.key, self.iv)
+    }
+
+    /// Generate random credentials from the entropy source of the OS. Key parts and IV
+    /// are below `encoding_size`, which is the size of your encoding. Weak credentials
+    /// (IV 0, or all key parts equal, like all-zero key) are never generated
+    /// # Example
+    /// ```
+    /// use tinystorm::key::Credentials;
+    ///
+    /// let credentials = Credentials::generate(42).unwrap();
+    /// assert!(!credentials.is_weak());
+    /// ```
+    pub fn generate(encoding_size: u16) -> Result<Self, CipherError> {
+        Self::generate_with(&mut OsRandom, encoding_size, true)
+    }
+
+    /// Generate random credentials with given random source. Every key part and IV is
+    /// uniformly distributed below `encoding_size`. If `avoid_weak` is set, weak credentials
+    /// are drawn again, see [`Credentials::is_weak`]
+    pub fn generate_with(
+        random: &mut impl RandomSource,
+        encoding_size: u16,
+        avoid_weak: bool,
+    ) -> Result<Self, CipherError> {
+        verify_encoding_size(encoding_size)?;
+        // Every credentials are weak with a single symbol
+        if avoid_weak && encoding_size < 2 {
+            return Err(CipherError::InvalidEncodingSize(encoding_size));
+        }
+
+        loop {
+            let mut values = [0; CHUNK_SIZE + 1];
+            for value in values.iter_mut() {
+                *value = random.below(encoding_size as u64) as u8;
+            }
+
+            let credentials = Self::new(&values[..CHUNK_SIZE], values[CHUNK_SIZE]);
+            if !avoid_weak || !credentials.is_weak() {
+                return Ok(credentials);
+            }
+        }
+    }
+
+    /// Returns true for known weak credentials: IV 0, with which every key value after
+    /// the first chunk is equal to the last key part, or key with all parts equal
+    pub fn is_weak(&self) -> bool {
+        self.iv == 0 || self.has_equal_parts()
+    }
+
+    /// Returns true if all key parts are equal, like all-zero key
+    pub(crate) fn has_equal_parts(&self) -> bool {
+        self.key.windows(2).all(|pair| pair[0] == pair[1])
+    }
+
+    /// Derive credentials from a passphrase, so they can be remembered as a sentence.
+    /// Key parts and IV are below `encoding_size`, which is the size of your encoding
+    /// ([`Cipher::get_encoding_bounds`](crate::cipher::Cipher::get_encoding_bounds)).
+    /// Same passphrase, salt and size always give the same credentials. Salt can be empty,
+    /// but with a salt equal passphrases of different users give different keys.
+    /// Uses [`DEFAULT_KDF_ROUNDS`] rounds, see [`Credentials::from_passphrase_with_rounds`]
+    /// # Example
+    /// ```
+    /// use tinystorm::key::Credentials;
+    ///
+    /// let passphrase = "correct horse battery staple";
+    /// let alice = Credentials::from_passphrase(passphrase, "alice", 42).unwrap();
+    /// let bob = Credentials::from_passphrase(passphrase, "bob", 42).unwrap();
+    ///
+    /// assert_eq!(alice, Credentials::from_passphrase(passphrase, "alice", 42).unwrap());
+    /// assert_ne!(alice, bob);
+    /// ```
+    pub fn from_passphrase(
+        passphrase: &str,
+        salt: &str,
+        encoding_size: u16,
+    ) -> Result<Self, CipherError> {
+        Self::from_passphrase_with_rounds(passphrase, salt, encoding_size, DEFAULT_KDF_ROUNDS)
+    }
+
+    /// Derive credentials from a passphrase with the given work factor: more rounds make
+    /// guessing the passphrase slower. The derivation is described in the README:
+    ///
+    /// 1. State is the 64-bit FNV-1a hash of salt bytes, 0xff byte and passphrase bytes
+    /// 2. Each round `r` (from 0) sets state to `mix(state ^ r)`
+    /// 3. Each of 4 key parts and then IV sets state to `mix(state)` and takes
+    ///    `state % encoding_size`
+    ///
+    /// Where `mix` is the SplitMix64 finalizer
+    pub fn from_passphrase_with_rounds(
+        passphrase: &str,
+        salt: &str,
+        encoding_size: u16,
+        rounds: u32,
+    ) -> Result<Self, CipherError> {
+        verify_encoding_size(encoding_size)?;
+
+        let mut state = FNV_OFFSET;
+        for &b in salt
+            .as_bytes()
+            .iter()
+            .chain(&[0xff])
+            .chain(passphrase.as_bytes())
+        {
+            state ^= b as u64;
+            state = state.wrapping_mul(FNV_PRIME);
+        }
+
+        for round in 0..rounds {
+            state = mix(state ^ round as u64);
+        }
+
+        // Bias of the remainder is negligible for a 64-bit state
+        let mut values = [0; CHUNK_SIZE + 1];
+        for value in values.iter_mut() {
+            state = mix(state);
+            *value = (state % encoding_size as u64) as u8;
+        }
+
+        Ok(Self::new(&values[..CHUNK_SIZE], values[CHUNK_SIZE]))
+    }
+
     /// Verify if IV and key parts don't exceed the encoding size which can lead to
     /// decryption problems
-    pub(crate) fn verify_credentials_size(&self, encoding_range: u8) -> Result<(), CipherError> {
-        if let Some(b) = self.key.iter().find(|&b| *b >= encoding_range) {
+    pub(crate) fn verify_credentials_size(&self, encoding_range: u16) -> Result<(), CipherError> {
+        if let Some(b) = self.key.iter().find(|&b| *b as u16 >= encoding_range) {
             return Err(CipherError::KeyOutOfBounds(*b, encoding_range));
         }
 
-        if self.iv >= encoding_range {
+        if self.iv as u16 >= encoding_range {
             return Err(CipherError::IVOutOfBounds(encoding_range));
         }
 
@@ -59,11 +239,11 @@ impl Credentials {
     /// println!("{:?}", buffer);
     /// // [1, 2, 3, 4, 31, 17, 3, 30, 16, 2, 29, 15, 1, 28, 14, 0]
     /// ```
-    pub fn expand_key(&self, buffer: &mut Vec<u8>, size: usize, range_mod: u8) {
+    pub fn expand_key(&self, buffer: &mut Vec<u8>, size: usize, range_mod: u16) {
         buffer.clear();
 
         // Pushing initial key to buffer as first key
-        buffer.append(&mut self.key.clone());
+        buffer.extend_from_slice(&self.key);
 
         // If size is only one chunk, we don't need to expand it anymore
         if size <= 1 {
@@ -75,23 +255,188 @@ impl Credentials {
             let mut key_with_iv = buffer
                 .last()
                 .iter()
-                .map(|&c| (c + self.iv) % range_mod)
+                .map(|&&c| add_mod(c, self.iv, range_mod))
                 .collect::<Vec<u8>>();
 
             // Second step: Swap
             swap_key(&mut key_with_iv, range_mod);
 
-            buffer.append(&mut key_with_iv);
+            buffer.extend_from_slice(&key_with_iv);
+            wipe(&mut key_with_iv);
+        }
+    }
+}
+
+impl Credentials {
+    /// Digits per part in the canonical string
+    fn part_width(&self) -> usize {
+        if self.key.iter().chain([&self.iv]).any(|&n| n > 99) {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// Check digit of the canonical string
+    fn check_digit(&self) -> u8 {
+        let width = self.part_width();
+        let digits = self
+            .key
+            .iter()
+            .chain([&self.iv])
+            .map(|n| format!("{n:0width$}"))
+            .collect::<String>();
+
+        luhn_check_digit(&digits)
+    }
+}
+
+impl Display for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self.part_width();
+        for (i, part) in self.key.iter().enumerate() {
+            if i > 0 {
+                write!(f, "-")?;
+            }
+            write!(f, "{part:0width$}")?;
+        }
+        write!(f, "/{:0width$}-{}", self.iv, self.check_digit())
+    }
+}
+
+impl FromStr for Credentials {
+    type Err = CipherError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s.chars().collect::<Vec<_>>();
+        let slash = chars
+            .iter()
+            .position(|&c| c == '/')
+            .ok_or(CipherError::MalformedCredentials(chars.len()))?;
+
+        // Key can be written with or without separators
+        let key = match digit_groups(&chars[..slash], 0)?.as_slice() {
+            [(position, digits)] if digits.len() == CHUNK_SIZE * 2 => {
+                split_part(digits, 2, *position)?
+            }
+            [(position, digits)] if digits.len() == CHUNK_SIZE * 3 => {
+                split_part(digits, 3, *position)?
+            }
+            groups if groups.len() == CHUNK_SIZE => groups
+                .iter()
+                .map(|(position, digits)| parse_part(digits, *position))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(CipherError::MalformedCredentials(slash)),
+        };
+
+        let rest = digit_groups(&chars[slash + 1..], slash + 1)?;
+        let [(iv_position, iv), (check_position, check)] = rest.as_slice() else {
+            return Err(CipherError::MalformedCredentials(chars.len()));
+        };
+        let (iv, check_position) = (parse_part(iv, *iv_position)?, *check_position);
+        if check.len() != 1 {
+            return Err(CipherError::MalformedCredentials(check_position));
+        }
+
+        let credentials = Credentials { key, iv };
+        if check.parse() != Ok(credentials.check_digit()) {
+            return Err(CipherError::CheckDigitMismatch(check_position));
         }
+
+        Ok(credentials)
+    }
+}
+
+/// Split chars to groups of digits separated by dashes or whitespaces.
+/// Returns position of each group in the whole string, `offset` is position of `chars`
+fn digit_groups(chars: &[char], offset: usize) -> Result<Vec<(usize, String)>, CipherError> {
+    let mut groups: Vec<(usize, String)> = Vec::new();
+    let mut in_group = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '0'..='9' if in_group => groups.last_mut().unwrap().1.push(c),
+            '0'..='9' => {
+                groups.push((offset + i, c.to_string()));
+                in_group = true;
+            }
+            '-' => in_group = false,
+            c if c.is_whitespace() => in_group = false,
+            _ => return Err(CipherError::MalformedCredentials(offset + i)),
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Parse a key part or IV written at `position`
+fn parse_part(digits: &str, position: usize) -> Result<u8, CipherError> {
+    if digits.len() > 3 {
+        return Err(CipherError::MalformedCredentials(position));
+    }
+    digits
+        .parse()
+        .map_err(|_| CipherError::MalformedCredentials(position))
+}
+
+/// Split key without separators to parts of `width` digits
+fn split_part(digits: &str, width: usize, position: usize) -> Result<Vec<u8>, CipherError> {
+    (0..CHUNK_SIZE)
+        .map(|i| parse_part(&digits[i * width..(i + 1) * width], position + i * width))
+        .collect()
+}
+
+/// Check digit of Luhn algorithm: every second digit from the right is doubled
+fn luhn_check_digit(digits: &str) -> u8 {
+    let sum = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let digit = (b - b'0') as u32;
+            match i % 2 {
+                0 if digit * 2 > 9 => digit * 2 - 9,
+                0 => digit * 2,
+                _ => digit,
+            }
+        })
+        .sum::<u32>();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// SplitMix64 finalizer, spreads every bit of the input over the output
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Check that key parts below `encoding_size` can be stored in `u8`
+pub(crate) fn verify_encoding_size(encoding_size: u16) -> Result<(), CipherError> {
+    if encoding_size == 0 || encoding_size > BYTE_MODE_SIZE {
+        return Err(CipherError::InvalidEncodingSize(encoding_size));
     }
+    Ok(())
 }
 
-/// Convert and verify credentials from strings
+/// Convert and verify credentials from strings.
+/// Key parts are written with 2 digits each, or with 3 digits each for byte mode
 pub(crate) fn parse_credentials<'a>(key: &'a str, iv: &'a str) -> Result<Credentials, CipherError> {
     let mut parsed_key: Vec<u8> = Vec::with_capacity(CHUNK_SIZE);
 
     let key_as_chars = key.chars().collect::<Vec<_>>();
-    let key_as_chunks = key_as_chars.chunks(2);
+    let part_width = if key_as_chars.len() == CHUNK_SIZE * 3 {
+        3
+    } else {
+        2
+    };
+    let key_as_chunks = key_as_chars.chunks(part_width);
 
     for chunk in key_as_chunks {
         let glued_chunk = chunk.iter().collect::<String>();
@@ -107,7 +452,7 @@ pub(crate) fn parse_credentials<'a>(key: &'a str, iv: &'a str) -> Result<Credent
         return Err(CipherError::InvalidKey);
     }
 
-    let iv_as_u8 = iv.parse::<u8>().map_err(CipherError::ParseIntError)?;
+    let iv_as_u8 = iv.parse::<u8>().map_err(|_| CipherError::InvalidIV)?;
 
     Ok(Credentials {
         key: parsed_key,
@@ -115,17 +460,27 @@ pub(crate) fn parse_credentials<'a>(key: &'a str, iv: &'a str) -> Result<Credent
     })
 }
 
+/// Overwrite buffer with zeros. Volatile writes can't be optimized out,
+/// even if the buffer is never read again
+pub(crate) fn wipe(buffer: &mut [u8]) {
+    for b in buffer.iter_mut() {
+        // SAFETY: `b` is a valid and aligned reference
+        unsafe { std::ptr::write_volatile(b, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
 /// Helper function for `extend_key`
 /// Has slightly different logic from regular chunk swap
-pub(crate) fn swap_key(buffer: &mut [u8], range_mod: u8) {
+pub(crate) fn swap_key(buffer: &mut [u8], range_mod: u16) {
     if buffer.len() < CHUNK_SIZE {
         return;
     }
 
     let [a, b, c, d] = [buffer[0], buffer[1], buffer[2], buffer[3]];
 
-    buffer[0] = (a + b) % range_mod;
-    buffer[1] = (buffer[0] + c) % range_mod;
-    buffer[2] = (c + d) % range_mod;
-    buffer[3] = (buffer[2] + b) % range_mod;
+    buffer[0] = add_mod(a, b, range_mod);
+    buffer[1] = add_mod(buffer[0], c, range_mod);
+    buffer[2] = add_mod(c, d, range_mod);
+    buffer[3] = add_mod(buffer[2], b, range_mod);
 }
@@ -0,0 +1,201 @@
+use crate::cipher::Cipher;
+use crate::encoding::{DEFAULT_ENCODING, EncodingType};
+use crate::envelope::{Envelope, is_valid_name};
+use crate::error::CipherError;
+use crate::key::Credentials;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Named credentials with their encoding
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    name: String,
+    credentials: Credentials,
+    encoding: EncodingType,
+}
+
+/// Set of named keys. Ciphertexts encrypted with the keyring have the name of the key
+/// in the header (`[kid=team;...]`), so the right key is picked on decryption.
+///
+/// Keyring is stored as a text file, one key per line: name, canonical credentials
+/// (see [`Credentials`]) and optional encoding name, ENv1 by default.
+/// Empty lines and lines starting with '#' are ignored
+/// ```text
+/// # Shared keys
+/// team = 25-21-08-40/39-0
+/// family = 12-35-07-29/47-6 RUv5
+/// ```
+/// # Example
+/// ```
+/// use tinystorm::error::CipherError;
+/// use tinystorm::keyring::Keyring;
+///
+/// let keyring: Keyring = "team = 25-21-08-40/39-0\nfamily = 12-35-07-29/47-6 RUv5"
+///     .parse()
+///     .unwrap();
+///
+/// let ciphertext = keyring.encrypt("family", "привет").unwrap();
+/// assert!(ciphertext.starts_with("[kid=family;enc=RUv5;fp="));
+/// assert_eq!(keyring.decrypt(&ciphertext).unwrap().trim(), "привет");
+///
+/// assert_eq!(
+///     keyring.encrypt("work", "hello"),
+///     Err(CipherError::UnknownKeyId("work".to_owned()))
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keyring {
+    entries: Vec<Entry>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load keyring from file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CipherError> {
+        std::fs::read_to_string(path)
+            .map_err(|e| CipherError::Io(e.kind()))?
+            .parse()
+    }
+
+    /// Save keyring to file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CipherError> {
+        std::fs::write(path, self.to_string()).map_err(|e| CipherError::Io(e.kind()))
+    }
+
+    /// Add credentials with given name. Name can consist of ASCII letters, digits,
+    /// '-' and '_', and credentials must fit the encoding
+    pub fn add(
+        &mut self,
+        name: &str,
+        credentials: Credentials,
+        encoding: EncodingType,
+    ) -> Result<(), CipherError> {
+        if !is_valid_name(name) {
+            return Err(CipherError::InvalidKeyId(name.to_owned()));
+        }
+        if self.entry(name).is_ok() {
+            return Err(CipherError::DuplicateKeyId(name.to_owned()));
+        }
+
+        // Check that credentials fit the encoding
+        Cipher::with_credentials(credentials.clone(), encoding)?;
+
+        self.entries.push(Entry {
+            name: name.to_owned(),
+            credentials,
+            encoding,
+        });
+        Ok(())
+    }
+
+    /// Remove key with given name
+    pub fn remove(&mut self, name: &str) -> Result<(), CipherError> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.name == name)
+            .ok_or_else(|| CipherError::UnknownKeyId(name.to_owned()))?;
+
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    /// Names of all keys in order they were added
+    pub fn list(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Cipher with the key of given name
+    pub fn get(&self, name: &str) -> Result<Cipher, CipherError> {
+        let entry = self.entry(name)?;
+        Cipher::with_credentials(entry.credentials.clone(), entry.encoding)
+    }
+
+    /// Encrypt plaintext with the key of given name and put the key name in the header
+    pub fn encrypt(&self, name: &str, plaintext: &str) -> Result<String, CipherError> {
+        let ciphertext = self.get(name)?.encrypt_with_header(plaintext)?;
+
+        let (envelope, body) = Envelope::split(&ciphertext);
+        let envelope = Envelope {
+            key_id: Some(name.to_owned()),
+            ..envelope.unwrap_or_default()
+        };
+        Ok(envelope.wrap(body))
+    }
+
+    /// Decrypt ciphertext with the key named in its header
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, CipherError> {
+        let (envelope, _) = Envelope::split(ciphertext);
+        let name = envelope
+            .and_then(|envelope| envelope.key_id)
+            .ok_or(CipherError::MissingKeyId)?;
+
+        self.get(&name)?.decrypt(ciphertext)
+    }
+
+    fn entry(&self, name: &str) -> Result<&Entry, CipherError> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| CipherError::UnknownKeyId(name.to_owned()))
+    }
+}
+
+impl Display for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{} = {} {}",
+                entry.name, entry.credentials, entry.encoding
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Keyring {
+    type Err = CipherError;
+
+    /// Parse keyring file, errors have the number of the line starting from 1
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut keyring = Keyring::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = || CipherError::MalformedKeyring(i + 1);
+            let (name, rest) = line.split_once('=').ok_or_else(malformed)?;
+            let rest = rest.trim();
+
+            // Encoding name is optional, credentials can have whitespaces
+            let (credentials, encoding) = match rest.rsplit_once(char::is_whitespace) {
+                Some((credentials, encoding)) => match encoding.parse() {
+                    Ok(encoding) => (credentials, encoding),
+                    Err(_) => (rest, DEFAULT_ENCODING),
+                },
+                None => (rest, DEFAULT_ENCODING),
+            };
+            let credentials = credentials.parse().map_err(|_| malformed())?;
+
+            keyring
+                .add(name.trim(), credentials, encoding)
+                .map_err(|e| match e {
+                    CipherError::DuplicateKeyId(_) => e,
+                    _ => malformed(),
+                })?;
+        }
+
+        Ok(keyring)
+    }
+}
@@ -1,6 +1,16 @@
+pub mod analysis;
 pub mod cipher;
 pub mod encoding;
+pub mod envelope;
 pub mod error;
+pub mod exchange;
+pub mod grapheme;
 pub mod key;
+pub mod keyring;
+pub mod mnemonic;
+pub mod normalization;
+pub mod random;
+pub mod rotation;
 #[cfg(test)]
 mod tests;
+pub mod transliteration;
@@ -0,0 +1,424 @@
+//! Mnemonic encoding of credentials: words are easier to read over the phone than numbers.
+//! Each key part and IV is a word from a list of 256 words, followed by a checksum word
+//! which catches wrong and swapped words
+
+use crate::cipher::CHUNK_SIZE;
+use crate::error::CipherError;
+use crate::key::{Credentials, verify_key};
+
+/// Word list of the mnemonic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    Russian,
+}
+
+impl Language {
+    fn words(&self) -> &'static [&'static str; 256] {
+        match self {
+            Language::English => &ENGLISH,
+            Language::Russian => &RUSSIAN,
+        }
+    }
+
+    /// Find word by its unambiguous prefix, first 4 letters are always enough
+    fn find(&self, prefix: &str) -> Result<u8, CipherError> {
+        let words = self.words();
+        if let Some(i) = words.iter().position(|&word| word == prefix) {
+            return Ok(i as u8);
+        }
+
+        let mut matches = words
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| word.starts_with(prefix));
+        match (matches.next(), matches.next()) {
+            (Some((i, _)), None) => Ok(i as u8),
+            (Some(_), Some(_)) => Err(CipherError::AmbiguousWord(prefix.to_owned())),
+            (None, _) => Err(CipherError::UnknownWord(prefix.to_owned())),
+        }
+    }
+}
+
+/// Write credentials as words separated by '-'. Returns an error if key doesn't have 4 parts
+/// # Example
+/// ```
+/// use tinystorm::key::Credentials;
+/// use tinystorm::mnemonic::{Language, from_words, to_words};
+///
+/// let credentials = Credentials::new(&[25, 21, 8, 40], 39);
+/// let words = to_words(&credentials, Language::English).unwrap();
+/// assert_eq!(words, "bishop-basket-angle-castle-carbon-angle");
+///
+/// // Any unambiguous prefix is accepted
+/// assert_eq!(from_words("bish bas ang cast carb angl"), Ok(credentials));
+/// ```
+pub fn to_words(credentials: &Credentials, language: Language) -> Result<String, CipherError> {
+    let words = language.words();
+    let values = values(credentials)?;
+
+    Ok(values
+        .iter()
+        .chain([&checksum(&values)])
+        .map(|&n| words[n as usize])
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+/// Parse credentials from words separated by '-' or whitespaces. Words can be shortened to
+/// unambiguous prefixes, the language is detected by the first word
+pub fn from_words(text: &str) -> Result<Credentials, CipherError> {
+    let text = text.to_lowercase();
+    let words = text
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    if words.len() != CHUNK_SIZE + 2 {
+        return Err(CipherError::InvalidKey);
+    }
+
+    let language = [Language::English, Language::Russian]
+        .into_iter()
+        .find(|language| language.find(words[0]).is_ok())
+        .unwrap_or(Language::English);
+
+    let mut values = [0; CHUNK_SIZE + 1];
+    for (value, word) in values.iter_mut().zip(&words) {
+        *value = language.find(word)?;
+    }
+    if language.find(words[CHUNK_SIZE + 1])? != checksum(&values) {
+        return Err(CipherError::InvalidChecksum);
+    }
+
+    Ok(Credentials::new(&values[..CHUNK_SIZE], values[CHUNK_SIZE]))
+}
+
+/// Key parts and IV
+fn values(credentials: &Credentials) -> Result<[u8; CHUNK_SIZE + 1], CipherError> {
+    if !verify_key(&credentials.key) {
+        return Err(CipherError::InvalidKey);
+    }
+
+    let mut values = [0; CHUNK_SIZE + 1];
+    values[..CHUNK_SIZE].copy_from_slice(&credentials.key);
+    values[CHUNK_SIZE] = credentials.iv;
+    Ok(values)
+}
+
+/// Weighted sum in GF(256) with weights 2^(i + 1). Weights are distinct, non-zero and not 1
+/// (the weight of the checksum word itself), so any single wrong word and any swap of two
+/// different words, checksum included, are caught
+fn checksum(values: &[u8]) -> u8 {
+    values
+        .iter()
+        .enumerate()
+        .fold(0, |sum, (i, &n)| sum ^ gf_mul(n, 2 << i))
+}
+
+/// Multiplication in GF(256) with the polynomial x^8 + x^4 + x^3 + x^2 + 1
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let overflow = a & 0x80 != 0;
+        a <<= 1;
+        if overflow {
+            a ^= 0x1d;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// English words, each of them has unique first 4 letters
+const ENGLISH: [&str; 256] = [
+    "able", "acorn", "adapt", "adult", "agree", "album", "alley", "amber", "angle", "apple",
+    "arena", "armor", "artist", "atlas", "audio", "autumn", "axis", "badge", "baker", "bamboo",
+    "banner", "basket", "beach", "bench", "bicycle", "bishop", "blossom", "boat", "border",
+    "bounce", "bread", "bridge", "bubble", "buffalo", "burger", "cabin", "cake", "canal", "canyon",
+    "carbon", "castle", "cave", "cement", "chair", "champion", "cherry", "chicken", "cinema",
+    "civil", "clever", "climb", "cloud", "coach", "cobra", "coffee", "comet", "coral", "cotton",
+    "country", "crab", "crane", "cream", "crystal", "cupboard", "dance", "dawn", "delta", "detail",
+    "dinner", "domain", "door", "dragon", "dream", "duck", "dust", "earth", "eclipse", "eight",
+    "elder", "elephant", "emerald", "engine", "escape", "exact", "fabric", "family", "farmer",
+    "fence", "festival", "field", "film", "fire", "flag", "flock", "fluid", "forest", "frame",
+    "frost", "fuel", "garden", "gate", "gentle", "ginger", "glacier", "globe", "goat", "gorilla",
+    "grass", "green", "habit", "harbor", "hawk", "hero", "history", "honey", "hotel", "hunter",
+    "idea", "image", "indoor", "inner", "island", "jacket", "jelly", "jigsaw", "judge", "jungle",
+    "kettle", "kitchen", "kiwi", "koala", "ladder", "lake", "lantern", "laser", "lawn", "leopard",
+    "level", "lily", "lion", "lizard", "locket", "lyric", "maple", "meadow", "memory", "meteor",
+    "milk", "mirror", "moment", "moon", "mosquito", "mountain", "mushroom", "napkin", "nation",
+    "needle", "nest", "noble", "north", "number", "nutmeg", "object", "october", "olive", "onion",
+    "orange", "orchid", "otter", "owner", "oyster", "palace", "panther", "parrot", "patrol",
+    "peanut", "pelican", "pepper", "picnic", "pilot", "pizza", "plastic", "poem", "pony", "potato",
+    "prism", "pyramid", "queen", "rabbit", "radio", "random", "razor", "region", "ribbon", "river",
+    "rocket", "royal", "ruby", "salad", "salt", "sand", "school", "season", "shell", "signal",
+    "simple", "skate", "slogan", "socket", "solar", "spider", "sponge", "square", "stable", "star",
+    "stone", "sugar", "sunset", "swan", "table", "talent", "tank", "taxi", "temple", "theater",
+    "ticket", "timber", "tomato", "topic", "tower", "traffic", "tree", "truck", "tunnel", "twelve",
+    "umbrella", "unicorn", "urban", "vacuum", "vanilla", "verb", "video", "violin", "vision",
+    "vivid", "voyage", "walnut", "water", "weasel", "wheat", "window", "wizard", "wonder", "world",
+    "yellow", "zebra",
+];
+
+/// Russian words, each of them has unique first 4 letters
+const RUSSIAN: [&str; 256] = [
+    "абрикос",
+    "автобус",
+    "адрес",
+    "аист",
+    "альбом",
+    "ананас",
+    "антенна",
+    "аптека",
+    "арена",
+    "астра",
+    "атлас",
+    "багаж",
+    "байдарка",
+    "банан",
+    "барабан",
+    "батон",
+    "бегемот",
+    "берег",
+    "библия",
+    "бинокль",
+    "бисер",
+    "благо",
+    "блокнот",
+    "бобр",
+    "бокал",
+    "болото",
+    "ботинок",
+    "брат",
+    "бриз",
+    "брюки",
+    "бубен",
+    "букет",
+    "бумага",
+    "бусы",
+    "буфет",
+    "бухта",
+    "бычок",
+    "валенок",
+    "варенье",
+    "ведро",
+    "веер",
+    "велосипед",
+    "верба",
+    "ветер",
+    "вещь",
+    "взгляд",
+    "вино",
+    "вишня",
+    "влага",
+    "вокзал",
+    "волк",
+    "ворона",
+    "время",
+    "выдра",
+    "галка",
+    "гамак",
+    "гвоздь",
+    "гитара",
+    "глина",
+    "гнездо",
+    "голубь",
+    "город",
+    "гранат",
+    "гроза",
+    "губка",
+    "гусь",
+    "дамба",
+    "дельфин",
+    "деталь",
+    "джунгли",
+    "дирижер",
+    "доброта",
+    "дозор",
+    "доска",
+    "дракон",
+    "дробь",
+    "дудка",
+    "дыня",
+    "егерь",
+    "енот",
+    "жаба",
+    "жезл",
+    "жемчуг",
+    "живот",
+    "жираф",
+    "забор",
+    "загадка",
+    "звезда",
+    "здание",
+    "зебра",
+    "земля",
+    "зерно",
+    "злак",
+    "знамя",
+    "золото",
+    "зубр",
+    "игрушка",
+    "изюм",
+    "иней",
+    "инжир",
+    "истина",
+    "кабан",
+    "калина",
+    "канат",
+    "капля",
+    "каток",
+    "квас",
+    "кеды",
+    "кино",
+    "кисть",
+    "ключ",
+    "кнопка",
+    "колесо",
+    "конь",
+    "копье",
+    "космос",
+    "кофе",
+    "краска",
+    "кролик",
+    "круг",
+    "кувшин",
+    "лава",
+    "лампа",
+    "лапа",
+    "ласточка",
+    "легенда",
+    "лето",
+    "липа",
+    "лодка",
+    "ложка",
+    "луна",
+    "любовь",
+    "магнит",
+    "малина",
+    "марка",
+    "мастер",
+    "медведь",
+    "место",
+    "метла",
+    "мешок",
+    "мозаика",
+    "мост",
+    "музыка",
+    "муравей",
+    "мышь",
+    "невод",
+    "новость",
+    "носок",
+    "нота",
+    "облако",
+    "овес",
+    "огурец",
+    "озеро",
+    "окно",
+    "омут",
+    "орел",
+    "осина",
+    "отец",
+    "охота",
+    "павлин",
+    "палатка",
+    "папка",
+    "паспорт",
+    "пастух",
+    "пенал",
+    "песня",
+    "печь",
+    "пирог",
+    "письмо",
+    "площадь",
+    "покой",
+    "почта",
+    "природа",
+    "пруд",
+    "пуговица",
+    "пчела",
+    "пятно",
+    "радуга",
+    "ракета",
+    "ребенок",
+    "ремень",
+    "рецепт",
+    "роза",
+    "рубин",
+    "ручей",
+    "рынок",
+    "салют",
+    "сани",
+    "сапог",
+    "свеча",
+    "седло",
+    "семья",
+    "серп",
+    "сестра",
+    "сигнал",
+    "синица",
+    "скала",
+    "скрипка",
+    "слон",
+    "снег",
+    "сокол",
+    "сорока",
+    "сосна",
+    "спорт",
+    "стена",
+    "страна",
+    "студент",
+    "стул",
+    "судьба",
+    "табак",
+    "танец",
+    "театр",
+    "телефон",
+    "теплица",
+    "тишина",
+    "топор",
+    "трава",
+    "трамвай",
+    "трубка",
+    "тыква",
+    "угол",
+    "узел",
+    "улей",
+    "умение",
+    "утка",
+    "учитель",
+    "фазан",
+    "фантик",
+    "фермер",
+    "флаг",
+    "форма",
+    "футбол",
+    "хвост",
+    "хлеб",
+    "холм",
+    "храм",
+    "художник",
+    "цапля",
+    "цель",
+    "церковь",
+    "цифра",
+    "чашка",
+    "человек",
+    "честь",
+    "шапка",
+    "шелк",
+    "шишка",
+    "школа",
+    "шоколад",
+    "шуба",
+    "щетка",
+    "экран",
+    "юбка",
+    "язык",
+    "январь",
+    "ясень",
+];
@@ -0,0 +1,324 @@
+//! Unicode normalization for chars that don't exist in the encoding table.
+//! Implements only the part of NFD/NFKD that matters for tinystorm encodings:
+//! latin letters with diacritics, combining marks and common compatibility forms
+
+/// Normalization applied by [`Encoder`] before encoding. Only chars that don't exist in
+/// the encoding table are changed, so 'й' stays 'й' with RUv5
+///
+/// [`Encoder`]: crate::encoding::Encoder
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Normalization {
+    /// Chars are left as is, unknown chars are removed by encoder
+    #[default]
+    Off,
+    /// Decompose chars and remove combining marks (like NFD): 'é' -> 'e', 'ß' -> "ss"
+    Decompose,
+    /// Same as `Decompose`, but also folds compatibility forms (like NFKD):
+    /// full-width letters, ligatures, superscript digits, etc.
+    Compatibility,
+}
+
+/// Single char replaced by normalization
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Position of the char in the original text, counted in chars
+    pub position: usize,
+    pub original: char,
+    /// Empty if the char was removed (combining marks)
+    pub replacement: String,
+}
+
+/// Normalized text with the report of changed chars
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Normalized {
+    pub text: String,
+    pub changes: Vec<Change>,
+}
+
+/// Latin letters with diacritics (Latin-1 Supplement and Latin Extended-A)
+const DECOMPOSITIONS: &[(char, &str)] = &[
+    ('À', "A"),
+    ('Á', "A"),
+    ('Â', "A"),
+    ('Ã', "A"),
+    ('Ä', "A"),
+    ('Å', "A"),
+    ('Æ', "AE"),
+    ('Ç', "C"),
+    ('È', "E"),
+    ('É', "E"),
+    ('Ê', "E"),
+    ('Ë', "E"),
+    ('Ì', "I"),
+    ('Í', "I"),
+    ('Î', "I"),
+    ('Ï', "I"),
+    ('Ð', "D"),
+    ('Ñ', "N"),
+    ('Ò', "O"),
+    ('Ó', "O"),
+    ('Ô', "O"),
+    ('Õ', "O"),
+    ('Ö', "O"),
+    ('Ø', "O"),
+    ('Ù', "U"),
+    ('Ú', "U"),
+    ('Û', "U"),
+    ('Ü', "U"),
+    ('Ý', "Y"),
+    ('Þ', "TH"),
+    ('ß', "ss"),
+    ('à', "a"),
+    ('á', "a"),
+    ('â', "a"),
+    ('ã', "a"),
+    ('ä', "a"),
+    ('å', "a"),
+    ('æ', "ae"),
+    ('ç', "c"),
+    ('è', "e"),
+    ('é', "e"),
+    ('ê', "e"),
+    ('ë', "e"),
+    ('ì', "i"),
+    ('í', "i"),
+    ('î', "i"),
+    ('ï', "i"),
+    ('ð', "d"),
+    ('ñ', "n"),
+    ('ò', "o"),
+    ('ó', "o"),
+    ('ô', "o"),
+    ('õ', "o"),
+    ('ö', "o"),
+    ('ø', "o"),
+    ('ù', "u"),
+    ('ú', "u"),
+    ('û', "u"),
+    ('ü', "u"),
+    ('ý', "y"),
+    ('þ', "th"),
+    ('ÿ', "y"),
+    ('Ā', "A"),
+    ('ā', "a"),
+    ('Ă', "A"),
+    ('ă', "a"),
+    ('Ą', "A"),
+    ('ą', "a"),
+    ('Ć', "C"),
+    ('ć', "c"),
+    ('Ĉ', "C"),
+    ('ĉ', "c"),
+    ('Ċ', "C"),
+    ('ċ', "c"),
+    ('Č', "C"),
+    ('č', "c"),
+    ('Ď', "D"),
+    ('ď', "d"),
+    ('Đ', "D"),
+    ('đ', "d"),
+    ('Ē', "E"),
+    ('ē', "e"),
+    ('Ĕ', "E"),
+    ('ĕ', "e"),
+    ('Ė', "E"),
+    ('ė', "e"),
+    ('Ę', "E"),
+    ('ę', "e"),
+    ('Ě', "E"),
+    ('ě', "e"),
+    ('Ĝ', "G"),
+    ('ĝ', "g"),
+    ('Ğ', "G"),
+    ('ğ', "g"),
+    ('Ġ', "G"),
+    ('ġ', "g"),
+    ('Ģ', "G"),
+    ('ģ', "g"),
+    ('Ĥ', "H"),
+    ('ĥ', "h"),
+    ('Ħ', "H"),
+    ('ħ', "h"),
+    ('Ĩ', "I"),
+    ('ĩ', "i"),
+    ('Ī', "I"),
+    ('ī', "i"),
+    ('Ĭ', "I"),
+    ('ĭ', "i"),
+    ('Į', "I"),
+    ('į', "i"),
+    ('İ', "I"),
+    ('ı', "i"),
+    ('Ĳ', "IJ"),
+    ('ĳ', "ij"),
+    ('Ĵ', "J"),
+    ('ĵ', "j"),
+    ('Ķ', "K"),
+    ('ķ', "k"),
+    ('Ĺ', "L"),
+    ('ĺ', "l"),
+    ('Ļ', "L"),
+    ('ļ', "l"),
+    ('Ľ', "L"),
+    ('ľ', "l"),
+    ('Ŀ', "L"),
+    ('ŀ', "l"),
+    ('Ł', "L"),
+    ('ł', "l"),
+    ('Ń', "N"),
+    ('ń', "n"),
+    ('Ņ', "N"),
+    ('ņ', "n"),
+    ('Ň', "N"),
+    ('ň', "n"),
+    ('ŉ', "'n"),
+    ('Ŋ', "N"),
+    ('ŋ', "n"),
+    ('Ō', "O"),
+    ('ō', "o"),
+    ('Ŏ', "O"),
+    ('ŏ', "o"),
+    ('Ő', "O"),
+    ('ő', "o"),
+    ('Œ', "OE"),
+    ('œ', "oe"),
+    ('Ŕ', "R"),
+    ('ŕ', "r"),
+    ('Ŗ', "R"),
+    ('ŗ', "r"),
+    ('Ř', "R"),
+    ('ř', "r"),
+    ('Ś', "S"),
+    ('ś', "s"),
+    ('Ŝ', "S"),
+    ('ŝ', "s"),
+    ('Ş', "S"),
+    ('ş', "s"),
+    ('Š', "S"),
+    ('š', "s"),
+    ('Ţ', "T"),
+    ('ţ', "t"),
+    ('Ť', "T"),
+    ('ť', "t"),
+    ('Ŧ', "T"),
+    ('ŧ', "t"),
+    ('Ũ', "U"),
+    ('ũ', "u"),
+    ('Ū', "U"),
+    ('ū', "u"),
+    ('Ŭ', "U"),
+    ('ŭ', "u"),
+    ('Ů', "U"),
+    ('ů', "u"),
+    ('Ű', "U"),
+    ('ű', "u"),
+    ('Ų', "U"),
+    ('ų', "u"),
+    ('Ŵ', "W"),
+    ('ŵ', "w"),
+    ('Ŷ', "Y"),
+    ('ŷ', "y"),
+    ('Ÿ', "Y"),
+    ('Ź', "Z"),
+    ('ź', "z"),
+    ('Ż', "Z"),
+    ('ż', "z"),
+    ('Ž', "Z"),
+    ('ž', "z"),
+    ('ſ', "s"),
+];
+
+/// Compatibility forms, full-width forms are handled separately
+const COMPATIBILITY: &[(char, &str)] = &[
+    ('\u{a0}', " "),
+    ('\u{3000}', " "),
+    ('¹', "1"),
+    ('²', "2"),
+    ('³', "3"),
+    ('…', "..."),
+    ('ﬀ', "ff"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('ﬃ', "ffi"),
+    ('ﬄ', "ffl"),
+    ('ﬅ', "st"),
+    ('ﬆ', "st"),
+];
+
+/// Returns true if `c` is a combining mark, which is removed by decomposition
+pub(crate) fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe20}'..='\u{fe2f}'
+    )
+}
+
+/// Fold single char. Returns `None` if normalization doesn't change the char
+/// # Example
+/// ```
+/// use tinystorm::normalization::{Normalization, fold_char};
+///
+/// assert_eq!(fold_char('é', Normalization::Decompose), Some("e".to_owned()));
+/// assert_eq!(fold_char('ß', Normalization::Decompose), Some("ss".to_owned()));
+/// assert_eq!(fold_char('Ａ', Normalization::Decompose), None);
+/// assert_eq!(fold_char('Ａ', Normalization::Compatibility), Some("A".to_owned()));
+/// ```
+pub fn fold_char(c: char, normalization: Normalization) -> Option<String> {
+    if normalization == Normalization::Off {
+        return None;
+    }
+
+    if is_combining_mark(c) {
+        return Some(String::new());
+    }
+
+    if let Some(&(_, decomposed)) = DECOMPOSITIONS.iter().find(|&&(ch, _)| ch == c) {
+        return Some(decomposed.to_owned());
+    }
+
+    if normalization != Normalization::Compatibility {
+        return None;
+    }
+
+    // Full-width forms of ASCII are shifted by a constant offset
+    if let '\u{ff01}'..='\u{ff5e}' = c {
+        return char::from_u32(c as u32 - 0xfee0).map(String::from);
+    }
+
+    COMPATIBILITY
+        .iter()
+        .find(|&&(ch, _)| ch == c)
+        .map(|&(_, folded)| folded.to_owned())
+}
+
+/// Normalize chars of `text` for which `is_known` returns false
+pub(crate) fn normalize(
+    text: &str,
+    normalization: Normalization,
+    is_known: impl Fn(char) -> bool,
+) -> Normalized {
+    let mut normalized = Normalized {
+        text: String::with_capacity(text.len()),
+        changes: Vec::new(),
+    };
+
+    for (position, c) in text.chars().enumerate() {
+        match fold_char(c, normalization).filter(|_| !is_known(c)) {
+            Some(replacement) => {
+                normalized.text.push_str(&replacement);
+                normalized.changes.push(Change {
+                    position,
+                    original: c,
+                    replacement,
+                });
+            }
+            None => normalized.text.push(c),
+        }
+    }
+
+    normalized
+}
@@ -0,0 +1,59 @@
+//! Sources of randomness for key generation, see [`Credentials::generate`](crate::key::Credentials::generate)
+
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of random numbers. Implement it to generate keys with your own RNG,
+/// for example a seeded one for reproducible exercises
+pub trait RandomSource {
+    /// Next uniformly distributed random number
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniformly distributed random number below `bound`. Uses rejection sampling,
+    /// so small numbers are not more likely than big ones as with plain remainder
+    /// # Example
+    /// ```
+    /// use tinystorm::random::{OsRandom, RandomSource};
+    ///
+    /// let dice = OsRandom.below(6) + 1;
+    /// assert!((1..=6).contains(&dice));
+    /// ```
+    fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+
+        // Largest multiple of `bound`, numbers from it are rejected
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < limit {
+                return n % bound;
+            }
+        }
+    }
+}
+
+/// Entropy source of the operating system: /dev/urandom, or randomly keyed SipHash
+/// of the current time on systems without it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRandom;
+
+impl RandomSource for OsRandom {
+    fn next_u64(&mut self) -> u64 {
+        let mut buffer = [0; 8];
+        let read = File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut buffer));
+        if read.is_ok() {
+            return u64::from_le_bytes(buffer);
+        }
+
+        // Each RandomState has its own random keys
+        let mut hasher = RandomState::new().build_hasher();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        hasher.write_u128(now.as_nanos());
+        hasher.finish()
+    }
+}
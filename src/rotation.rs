@@ -0,0 +1,88 @@
+//! Key rotation: re-encrypt ciphertexts under new credentials without turning
+//! the plaintext into a `String`
+
+use crate::cipher::Cipher;
+use crate::encoding::Encoding;
+use crate::envelope::Envelope;
+use crate::error::CipherError;
+
+/// Decrypt ciphertext with `old` cipher and encrypt it with `new` one. Works with encoded
+/// symbols, which are translated if the ciphers use different encodings.
+/// Header of the ciphertext is kept: key ID stays the same, encoding name and fingerprint
+/// are updated. Ciphertext without header gets one only if `new` is in strict mode
+/// # Example
+/// ```
+/// use tinystorm::cipher::Cipher;
+/// use tinystorm::rotation::rekey;
+///
+/// let old = Cipher::new("25211840", "39").unwrap();
+/// let new = Cipher::new("01020304", "05").unwrap();
+///
+/// let ciphertext = old.encrypt("hello, world!").unwrap();
+/// let rekeyed = rekey(&old, &new, &ciphertext).unwrap();
+/// assert_eq!(new.decrypt(&rekeyed).unwrap().trim(), "hello, world!");
+/// ```
+pub fn rekey(old: &Cipher, new: &Cipher, ciphertext: &str) -> Result<String, CipherError> {
+    let (envelope, _) = Envelope::split(ciphertext);
+    let (encoder, mut symbols) = old.decrypt_to_symbols(ciphertext)?;
+
+    let from = &encoder.table;
+    let to = &new.encoder().table;
+    if from.fingerprint() != to.fingerprint() {
+        translate(from, to, &mut symbols)?;
+    }
+
+    let body = new.encrypt_symbols(symbols)?;
+    match envelope {
+        Some(envelope) => {
+            let envelope = Envelope {
+                key_id: envelope.key_id,
+                ..new.envelope()
+            };
+            Ok(envelope.wrap(&body))
+        }
+        None => Ok(new.seal(body)),
+    }
+}
+
+/// Re-encrypt every ciphertext, see [`rekey`]. Failed ciphertexts don't stop the batch,
+/// result of each one is returned in the same order
+/// # Example
+/// ```
+/// use tinystorm::cipher::Cipher;
+/// use tinystorm::error::CipherError;
+/// use tinystorm::rotation::rekey_all;
+///
+/// let mut old = Cipher::new("25211840", "39").unwrap();
+/// old.set_strict(true);
+/// let new = Cipher::new("01020304", "05").unwrap();
+///
+/// let messages = [old.encrypt("first").unwrap(), "no header".to_owned()];
+/// let results = rekey_all(&old, &new, &messages);
+/// assert!(results[0].is_ok());
+/// assert_eq!(results[1], Err(CipherError::MissingFingerprint));
+/// ```
+pub fn rekey_all<I>(old: &Cipher, new: &Cipher, ciphertexts: I) -> Vec<Result<String, CipherError>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    ciphertexts
+        .into_iter()
+        .map(|ciphertext| rekey(old, new, ciphertext.as_ref()))
+        .collect()
+}
+
+/// Replace codes of `from` encoding with codes of the same symbols in `to` encoding
+fn translate(from: &Encoding, to: &Encoding, symbols: &mut [u8]) -> Result<(), CipherError> {
+    for code in symbols.iter_mut() {
+        let translated = match from.sequence_of(*code) {
+            Some(sequence) => to.sequences().find(|&(s, _)| s == sequence).map(|(_, n)| n),
+            None => from.char_of(*code).and_then(|c| to.code_of(c)),
+        };
+
+        *code = translated.ok_or(CipherError::UntranslatableSymbol(*code))?;
+    }
+
+    Ok(())
+}
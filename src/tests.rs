@@ -1,13 +1,24 @@
+use crate::analysis::keys::{Weakness, analyze, keyspace};
 use crate::cipher::{CHUNK_SIZE, Cipher, adjust_chunks};
-use crate::encoding::{Encoder, Encoding, EncodingType};
+use crate::encoding::{CaseMask, Encoder, Encoding, EncodingType, Registry};
+use crate::encoding_from_str;
 use crate::encoding_table;
-use crate::key::Credentials;
+use crate::error::CipherError;
+use crate::exchange::{Group, Party, Transcript, derive_credentials};
+use crate::grapheme::clusters;
+use crate::key::{Credentials, wipe};
+use crate::keyring::Keyring;
+use crate::mnemonic::{Language, from_words, to_words};
+use crate::normalization::Normalization;
+use crate::random::RandomSource;
+use crate::rotation::{rekey, rekey_all};
+use crate::transliteration::{Transliteration, to_cyrillic};
 
 #[test]
 fn encryption_test() {
     let plaintext = "Buzzy fuzz!";
     let mut cipher = Cipher::new("25211840", "39").unwrap();
-    cipher.set_encoder(EncodingType::ENv2);
+    cipher.set_encoder(EncodingType::ENv2).unwrap();
 
     let ciphertext = cipher.encrypt(plaintext).unwrap();
     assert_eq!(ciphertext, "gzZQ8izmBOEw");
@@ -20,8 +31,7 @@ fn encryption_test() {
 #[test]
 fn encryption_test_with_russian_encoding() {
     let plaintext = "привет, мир!";
-    let mut cipher = Cipher::new("12350729", "47").unwrap();
-    cipher.set_encoder(EncodingType::RUv5);
+    let cipher = Cipher::with_encoding("12350729", "47", EncodingType::RUv5).unwrap();
 
     let ciphertext = cipher.encrypt(plaintext).unwrap();
     assert_eq!(ciphertext, "щы1зм7 щд?ит.62ю");
@@ -31,6 +41,284 @@ fn encryption_test_with_russian_encoding() {
     assert_eq!(decrypted.trim(), plaintext);
 }
 
+#[test]
+fn validated_construction_test() {
+    // ENv1 has 42 symbols
+    assert_eq!(
+        Cipher::new("12350729", "47"),
+        Err(CipherError::IVOutOfBounds(42))
+    );
+    assert_eq!(
+        Cipher::from(&[12, 50, 7, 29], 3),
+        Err(CipherError::KeyOutOfBounds(50, 42))
+    );
+    assert_eq!(Cipher::new("12350729", "4x"), Err(CipherError::InvalidIV));
+
+    let credentials: Credentials = "12-35-07-29/47-6".parse().unwrap();
+    let mut cipher = Cipher::with_credentials(credentials, EncodingType::RUv5).unwrap();
+
+    // Encoding stays the same if credentials don't fit the new one
+    assert_eq!(
+        cipher.set_encoder(EncodingType::RUv4),
+        Err(CipherError::IVOutOfBounds(41))
+    );
+    let small = encoding_from_str!(" abc");
+    assert_eq!(
+        cipher.load_encoder(small, false),
+        Err(CipherError::KeyOutOfBounds(12, 4))
+    );
+    assert_eq!(cipher.get_encoding_bounds(), 51);
+    assert_eq!(cipher.encrypt("привет, мир!").unwrap(), "щы1зм7 щд?ит.62ю");
+}
+
+#[test]
+fn credentials_key_length_test() {
+    // Key expansion never ends with empty key
+    for key in [&[][..], &[1, 2, 3]] {
+        let credentials = Credentials::new(key, 5);
+        assert_eq!(analyze(&credentials, 42), Err(CipherError::InvalidKey));
+        assert_eq!(
+            Cipher::with_credentials(credentials.clone(), EncodingType::ENv1),
+            Err(CipherError::InvalidKey)
+        );
+
+        assert_eq!(
+            to_words(&credentials, Language::English),
+            Err(CipherError::InvalidKey)
+        );
+
+        let mut keyring = Keyring::new();
+        assert_eq!(
+            keyring.add("team", credentials, EncodingType::ENv1),
+            Err(CipherError::InvalidKey)
+        );
+    }
+}
+
+#[test]
+fn case_preserving_encryption_test() {
+    let plaintext = "Привет, Мир!";
+    let cipher = Cipher::with_encoding("12350729", "47", EncodingType::RUv5).unwrap();
+
+    let (ciphertext, mask) = cipher.encrypt_preserving_case(plaintext).unwrap();
+    // Case mask doesn't affect the ciphertext itself
+    assert_eq!(ciphertext, cipher.encrypt(plaintext).unwrap());
+
+    // Mask survives being stored as text
+    let mask: CaseMask = mask.to_string().parse().unwrap();
+    assert_eq!(mask.to_string(), "808");
+
+    let decrypted = cipher.decrypt_with_case(&ciphertext, &mask).unwrap();
+    assert_eq!(decrypted.trim(), plaintext);
+}
+
+#[test]
+fn encoding_registry_test() {
+    assert_eq!("env1".parse(), Ok(EncodingType::ENv1));
+    assert_eq!("en".parse(), Ok(EncodingType::ENv2));
+    assert_eq!(" uk ".parse(), Ok(EncodingType::UKv1));
+    assert_eq!(
+        "xx".parse::<EncodingType>(),
+        Err(CipherError::UnknownEncoding("xx".to_owned()))
+    );
+
+    for &encoding in EncodingType::list() {
+        assert_eq!(encoding.to_string().parse(), Ok(encoding));
+    }
+
+    let plaintext = "größe, ящірка, ¿qué?";
+    let samples = [
+        (EncodingType::DEv1, "größe"),
+        (EncodingType::UKv1, "ящірка"),
+        (EncodingType::ESv1, "¿qué?"),
+    ];
+    for (encoding, expected) in samples {
+        let encoder = Encoder::new(encoding);
+        let decoded = encoder.decode(&encoder.encode(plaintext));
+        assert!(decoded.contains(expected), "{encoding}: {decoded}");
+    }
+
+    // Names are written in headers, so they can't have header syntax
+    let mut registry = Registry::new();
+    for name in ["my]table", "a;b", "enc=x", ""] {
+        assert_eq!(
+            registry.register(name, EncodingType::ENv1.encoding(), false),
+            Err(CipherError::InvalidEncodingName(name.to_owned()))
+        );
+    }
+    registry
+        .register("my-table_2", EncodingType::ENv1.encoding(), false)
+        .unwrap();
+}
+
+#[test]
+fn encoding_detection_test() {
+    let detected = Encoder::detect("hello");
+    assert_eq!(detected[0].name, "ENv1");
+    // Uppercase support breaks the tie
+    let detected = Encoder::detect("Hello");
+    assert_eq!(detected[0].name, "ENv2");
+    assert!(detected[0].is_complete());
+
+    // Header picks encoding automatically
+    let sender = Cipher::with_encoding("12350729", "47", EncodingType::RUv5).unwrap();
+    let ciphertext = sender.encrypt_with_header("привет, мир!").unwrap();
+    assert_eq!(ciphertext, "[enc=RUv5;fp=37dd6b83]щы1зм7 щд?ит.62ю");
+
+    let mut receiver = Cipher::with_encoding("12350729", "47", EncodingType::ENv2).unwrap();
+    receiver.set_auto_encoding(Some(Registry::new()));
+    assert_eq!(
+        receiver.decrypt(&ciphertext).unwrap().trim(),
+        "привет, мир!"
+    );
+
+    let unknown = "[enc=XXv1]щы1зм7 щд?ит.62ю";
+    assert_eq!(
+        receiver.decrypt(unknown),
+        Err(CipherError::UnknownEncoding("XXv1".to_owned()))
+    );
+}
+
+#[test]
+fn bracketed_ciphertext_test() {
+    // Ciphertext of ENv3 can start with brackets, which are not a header
+    let cipher = Cipher::with_encoding("25211840", "39", EncodingType::ENv3).unwrap();
+    let ciphertext = cipher.encrypt(" 0cx").unwrap();
+    assert_eq!(ciphertext, "[]nL}J)F");
+    assert_eq!(cipher.decrypt(&ciphertext).unwrap().trim_end(), " 0cx");
+}
+
+#[test]
+fn keyed_encoding_test() {
+    let keyed = Encoding::keyed(&EncodingType::ENv1.encoding(), "KRYPTOS");
+    let encoder = Encoder::load(keyed, false).unwrap();
+    assert_eq!(encoder.size, 42);
+
+    // Space stays first for padding, then keyword letters and the rest of the table
+    assert_eq!(encoder.encode("krypos a"), vec![1, 2, 3, 4, 6, 7, 0, 8]);
+    assert_eq!(encoder.encode("b-"), vec![9, 41]);
+
+    // Different keyword gives different ciphertext for the same credentials
+    let mut first = Cipher::new("25211840", "39").unwrap();
+    first
+        .load_encoder(
+            Encoding::keyed(&EncodingType::ENv1.encoding(), "tiger"),
+            false,
+        )
+        .unwrap();
+    let mut second = Cipher::new("25211840", "39").unwrap();
+    second
+        .load_encoder(
+            Encoding::keyed(&EncodingType::ENv1.encoding(), "zebra"),
+            false,
+        )
+        .unwrap();
+    assert_ne!(
+        first.encrypt("attack at dawn").unwrap(),
+        second.encrypt("attack at dawn").unwrap()
+    );
+
+    // Keyed table is a part of the secret, so it isn't printed
+    let debug = format!("{first:?}");
+    assert!(debug.contains("inner: <redacted>"), "{debug}");
+    assert!(!debug.contains("('t', 1)"), "{debug}");
+    let derived = Encoding::keyed(&EncodingType::ENv1.encoding(), "tiger").subset(|c| c != 'z');
+    assert!(format!("{:?}", derived.unwrap()).contains("inner: <redacted>"));
+    assert!(format!("{:?}", EncodingType::ENv1.encoding()).contains("('a', 1)"));
+}
+
+#[test]
+fn encoding_composition_test() {
+    const MY_TABLE: Encoding = encoding_table!(
+        [(' ', 0), ('c', 1), ('h', 2), ('-', 3)],
+        sequences: [("ch", 4)],
+        aliases: [('—', '-')]
+    );
+
+    // Union keeps symbols of the first table and renumbers the rest
+    let merged = Encoding::merge(&MY_TABLE, &EncodingType::ENv1.encoding()).unwrap();
+    let encoder = Encoder::load(merged, false).unwrap();
+    assert_eq!(encoder.size, 42 + 1);
+    assert_eq!(encoder.encode("ch — a"), vec![4, 0, 3, 0, 5]);
+
+    // Sequence is removed together with its char, alias together with its target
+    let subset = MY_TABLE.subset(|c| c != 'h' && c != '-').unwrap();
+    let encoder = Encoder::load(subset, false).unwrap();
+    assert_eq!(encoder.size, 2);
+    assert_eq!(encoder.encode("ch —"), vec![1, 0]);
+
+    assert_eq!(
+        MY_TABLE.subset(|_| false),
+        Err(CipherError::MalformedEncoding)
+    );
+    assert_eq!(
+        MY_TABLE.extend(&['x', 'x']),
+        Err(CipherError::MalformedEncoding)
+    );
+}
+
+#[test]
+fn encoding_introspection_test() {
+    let mut encoder = Encoder::new(EncodingType::RUv5);
+    assert!(!encoder.supports_uppercase());
+    assert_eq!(
+        encoder.coverage("Ёжик — hedgehog"),
+        vec!['h', 'e', 'd', 'g', 'o']
+    );
+
+    encoder.set_transliteration(Transliteration::Gost);
+    assert!(encoder.coverage("Ёжик — hedgehog").is_empty());
+
+    let table = &encoder.table;
+    assert_eq!(table.len(), table.iter().count());
+    assert_eq!(table.code_of('—'), table.code_of('-'));
+    assert_eq!(table.char_of(table.code_of('—').unwrap()), Some('-'));
+    assert_eq!(table.char_of(200), None);
+}
+
+#[test]
+fn encoding_from_str_test() {
+    const MY_TABLE: Encoding = encoding_from_str!(" aé€😀", sequences: [("ae", 5)]);
+    let encoder = Encoder::load(MY_TABLE, false).unwrap();
+    assert_eq!(encoder.size, 6);
+
+    // 1, 2, 3 and 4 bytes long chars
+    assert_eq!(
+        encoder.encode("a é € 😀 ae"),
+        vec![1, 0, 2, 0, 3, 0, 4, 0, 5]
+    );
+    assert_eq!(MY_TABLE.char_of(4), Some('😀'));
+}
+
+#[test]
+fn encoding_fingerprint_test() {
+    // Fingerprints are stable, distinct and depend on the order of symbols
+    assert_eq!(EncodingType::RUv5.encoding().fingerprint(), 0x37dd6b83);
+    let mut fingerprints = EncodingType::list()
+        .iter()
+        .map(|e| e.encoding().fingerprint())
+        .collect::<Vec<_>>();
+    fingerprints.sort_unstable();
+    fingerprints.dedup();
+    assert_eq!(fingerprints.len(), EncodingType::list().len());
+
+    const ABC: Encoding = encoding_from_str!("abc");
+    const ACB: Encoding = encoding_from_str!("acb");
+    assert_ne!(ABC.fingerprint(), ACB.fingerprint());
+
+    // Header fingerprint is checked even without strict mode
+    let mut cipher = Cipher::with_encoding("12350729", "47", EncodingType::RUv5).unwrap();
+    let ciphertext = cipher.encrypt_with_header("привет").unwrap();
+
+    cipher.set_encoder(EncodingType::ENv2).unwrap();
+    assert_eq!(
+        cipher.decrypt(&ciphertext),
+        Err(CipherError::EncodingMismatch)
+    );
+    // Plain ciphertext is still accepted
+    assert!(cipher.decrypt("abcd").is_ok());
+}
+
 #[test]
 fn encoding_test() {
     let encoder = Encoder::new(EncodingType::ENv1);
@@ -52,6 +340,600 @@ fn custom_encoding_test() {
     assert_eq!(encoded, vec![1, 2, 3]);
 }
 
+#[test]
+fn encoding_aliases_test() {
+    const MY_TABLE: Encoding = encoding_table!(
+        [(' ', 0), ('е', 1), ('ж', 2), ('"', 3)],
+        aliases: [('ё', 'е'), ('«', '"'), ('»', '"')]
+    );
+    let encoder = Encoder::load(MY_TABLE, false).unwrap();
+
+    let encoded = encoder.encode("«ёж»");
+    assert_eq!(encoded, vec![3, 1, 2, 3]);
+    // Aliases are never produced by decoder
+    assert_eq!(encoder.decode(&encoded), "\"еж\"");
+
+    assert!(!crate::encoding::check_for_malformed_aliases(
+        &[('a', 0), ('b', 1)],
+        &[('a', 'b')]
+    ));
+    assert!(!crate::encoding::check_for_malformed_aliases(
+        &[('a', 0)],
+        &[('c', 'z')]
+    ));
+}
+
+#[test]
+fn encoding_sequences_test() {
+    const MY_TABLE: Encoding = encoding_table!(
+        [(' ', 0), ('c', 1), ('h', 2), ('s', 3), ('\n', 4)],
+        sequences: [("ch", 5), ("sch", 6), ("\r\n", 7)]
+    );
+    let encoder = Encoder::load(MY_TABLE, false).unwrap();
+
+    // Longest match wins: "sch" is preferred over 's' + "ch"
+    let encoded = encoder.encode("Chess sch\r\nc h");
+    assert_eq!(encoded, vec![5, 3, 3, 0, 6, 7, 1, 0, 2]);
+    assert_eq!(encoder.decode(&encoded), "chss sch\r\nc h");
+
+    let (encoded, mask) = encoder.encode_preserving_case("Chess");
+    assert_eq!(encoder.decode_with_case(&encoded, &mask), "Chss");
+
+    // Repeated sequence
+    assert!(!crate::encoding::check_for_malformed_sequences(
+        &[('a', 0)],
+        &[("ab", 1), ("ab", 2)]
+    ));
+    // Single char sequence
+    assert!(!crate::encoding::check_for_malformed_sequences(
+        &[('a', 0)],
+        &[("b", 1)]
+    ));
+    // Sequence number is taken by a char
+    assert!(!crate::encoding::check_for_malformed_sequences(
+        &[('a', 0)],
+        &[("ab", 0)]
+    ));
+}
+
+#[test]
+fn sequence_ciphertext_test() {
+    const MY_TABLE: Encoding = encoding_table!(
+        [(' ', 0), ('c', 1), ('h', 2), ('s', 3), ('a', 4)],
+        sequences: [("ch", 5)]
+    );
+    let mut cipher = Cipher::from(&[1, 2, 3, 4], 1).unwrap();
+    cipher.load_encoder(MY_TABLE, false).unwrap();
+
+    // 'c' and 'h' next to each other in the ciphertext are separated from "ch"
+    let ciphertext = cipher.encrypt("cash").unwrap();
+    assert_eq!(ciphertext, "saacch c·h");
+    assert_eq!(cipher.decrypt(&ciphertext).unwrap().trim(), "cash");
+
+    for word in [
+        "cash", "chacha", "ash", "hash", "cha", "achc", "chch", "sch", "hc", "c h",
+    ] {
+        let ciphertext = cipher.encrypt(word).unwrap();
+        assert_eq!(
+            cipher.decrypt(&ciphertext).unwrap().trim(),
+            word,
+            "{ciphertext}"
+        );
+    }
+
+    // Separator can't be a symbol of the table with sequences
+    const RESERVED: Encoding =
+        encoding_table!([(' ', 0), ('·', 1), ('c', 2)], sequences: [("cc", 3)]);
+    assert_eq!(
+        Encoder::load(RESERVED, false),
+        Err(CipherError::ReservedSeparator('·'))
+    );
+}
+
+#[test]
+fn grapheme_clusters_test() {
+    assert_eq!(clusters("🇺🇦👩🏽\r\né"), vec!["🇺🇦", "👩🏽", "\r\n", "é"]);
+
+    const CHAT: Encoding = encoding_table!(
+        [(' ', 0), ('h', 1), ('i', 2), ('👍', 3), ('e', 4)],
+        sequences: [("👍🏽", 5), ("👨‍👩‍👧", 6), ("👨‍👩", 7)]
+    );
+    let mut encoder = Encoder::load(CHAT, false).unwrap();
+
+    // Char mode splits the family and the thumb with unknown skin tone
+    let encoded = encoder.encode("hi 👨‍👩‍👦👍🏿");
+    assert_eq!(encoded, vec![1, 2, 0, 7, 3]);
+
+    // Cluster mode keeps whole clusters or drops them entirely
+    encoder.set_grapheme_clusters(true);
+    let encoded = encoder.encode("hi 👨‍👩‍👦👍🏿👍🏽👨‍👩‍👧e\u{301}");
+    assert_eq!(encoded, vec![1, 2, 0, 5, 6]);
+    assert_eq!(encoder.decode(&encoded), "hi 👍🏽👨‍👩‍👧");
+
+    assert_eq!(
+        encoder.coverage("👍🏿e\u{301}"),
+        vec!['👍', '🏿', 'e', '\u{301}']
+    );
+
+    // CRLF keeps the newline even if the table doesn't have '\r'
+    let mut encoder = Encoder::new(EncodingType::ENv3);
+    encoder.set_grapheme_clusters(true);
+    assert_eq!(encoder.decode(&encoder.encode("a\r\nb")), "a\nb");
+    assert_eq!(encoder.coverage("a\r\nb"), vec!['\r']);
+
+    // Clusters only apply to plaintext: neighbouring ciphertext symbols aren't merged
+    const EMOJI: Encoding = encoding_table!(
+        [(' ', 0), ('a', 1), ('👍', 2), ('🏽', 3), ('b', 4)],
+        sequences: [("👍🏽", 5)]
+    );
+    let mut cipher = Cipher::from(&[1, 2, 3, 4], 1).unwrap();
+    cipher.load_encoder(EMOJI, false).unwrap();
+    cipher.set_grapheme_clusters(true);
+    for plaintext in ["ba b", "a👍🏽", "👍🏽b👍🏽 a", "🏽👍"] {
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        let decrypted = cipher.decrypt(&ciphertext).unwrap();
+        assert_eq!(decrypted.trim_end(), plaintext, "{ciphertext}");
+    }
+}
+
+#[test]
+fn normalization_test() {
+    let mut encoder = Encoder::new(EncodingType::RUv5);
+    encoder.set_normalization(Normalization::Compatibility);
+
+    // 'й' exists in RUv5 and must not be decomposed, 'é' and full-width digit are folded
+    let normalized = encoder.normalize("йé１");
+    assert_eq!(normalized.text, "йe1");
+    assert_eq!(normalized.changes.len(), 2);
+    assert_eq!(normalized.changes[1].position, 2);
+    assert_eq!(normalized.changes[1].replacement, "1");
+
+    // Combining marks of already decomposed text are removed
+    let mut encoder = Encoder::new(EncodingType::ENv2);
+    encoder.set_normalization(Normalization::Decompose);
+    let encoded = encoder.encode("Ma\u{301}laga, Stra\u{df}e");
+    assert_eq!(encoder.decode(&encoded), "Malaga, Strasse");
+}
+
+#[test]
+fn transliteration_test() {
+    let plaintext = "Привет, Hello!";
+    let mut cipher = Cipher::new("25211840", "39").unwrap();
+    cipher.set_transliteration(Transliteration::Gost);
+
+    let ciphertext = cipher.encrypt(plaintext).unwrap();
+    let decrypted = cipher.decrypt(&ciphertext).unwrap();
+    assert_eq!(decrypted.trim(), "privet, hello!");
+    assert_eq!(to_cyrillic(decrypted.trim()), "привет, хелло!");
+
+    let mut encoder = Encoder::new(EncodingType::RUv5);
+    encoder.set_transliteration(Transliteration::Gost);
+    let encoded = encoder.encode("Shhuka i yozh");
+    assert_eq!(encoder.decode(&encoded), "щука и ёж");
+
+    // Latin tables don't have backticks of GOST forms, so simpler forms are used.
+    // ENv1 and ENv2 don't have apostrophe either, so only soft and hard signs are dropped
+    let text = "мы здесь, это объект";
+    let samples = [
+        (EncodingType::ENv1, "my zdes, eto obekt", vec!['ь', 'ъ']),
+        (EncodingType::ENv2, "my zdes, eto obekt", vec!['ь', 'ъ']),
+        (EncodingType::ENv3, "my zdes', eto ob'ekt", vec![]),
+    ];
+    for (encoding, expected, dropped) in samples {
+        let mut encoder = Encoder::new(encoding);
+        encoder.set_transliteration(Transliteration::Gost);
+        assert_eq!(
+            encoder.decode(&encoder.encode(text)),
+            expected,
+            "{encoding}"
+        );
+        assert_eq!(encoder.coverage(text), dropped, "{encoding}");
+    }
+}
+
+#[test]
+fn line_mode_test() {
+    // Document tables aren't picked by language code
+    assert_eq!("ru".parse(), Ok(EncodingType::RUv5));
+    assert!(EncodingType::RUv6.is_document());
+
+    let plaintext = "Заметка:\n\t«Пункт 1» (важно)\r\n\nКонец";
+    let mut cipher = Cipher::new("25211840", "39").unwrap();
+    cipher.set_encoder(EncodingType::RUv6).unwrap();
+
+    // Document table keeps the text in regular mode too, except for '\r'
+    let ciphertext = cipher.encrypt(plaintext).unwrap();
+    assert_eq!(
+        cipher.decrypt(&ciphertext).unwrap().trim_end(),
+        plaintext.replace('\r', "")
+    );
+
+    let ciphertext = cipher.encrypt_lines(plaintext).unwrap();
+    let lines = ciphertext.split('\n').collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].ends_with('\r'));
+    assert_eq!(lines[2], "");
+    assert_eq!(cipher.decrypt_lines(&ciphertext).unwrap(), plaintext);
+
+    // Only the changed line differs
+    let edited = cipher
+        .encrypt_lines("Заметка:\n\t«Пункт 2» (важно)\r\n\nКонец")
+        .unwrap();
+    let changed = edited
+        .split('\n')
+        .zip(lines)
+        .filter(|(a, b)| a != b)
+        .count();
+    assert_eq!(changed, 1);
+
+    // Header goes in front of the first line
+    cipher.set_strict(true);
+    let ciphertext = cipher.encrypt_lines(plaintext).unwrap();
+    assert!(ciphertext.starts_with("[enc=RUv6;fp="));
+    assert_eq!(cipher.decrypt_lines(&ciphertext).unwrap(), plaintext);
+}
+
+#[test]
+fn byte_mode_test() {
+    let cipher = Cipher::with_encoding("250210108255", "199", EncodingType::Bytes).unwrap();
+
+    // Whole chunk of padding is added to aligned data
+    let data = (0..=255).collect::<Vec<u8>>();
+    let encrypted = cipher.encrypt_bytes(&data).unwrap();
+    assert_eq!(encrypted.len(), 260);
+    assert_eq!(cipher.decrypt_bytes(&encrypted).unwrap(), data);
+
+    assert_eq!(
+        cipher.decrypt_bytes(&encrypted[..3]),
+        Err(CipherError::InvalidPadding)
+    );
+
+    // Byte mode as encoding: every Latin-1 char is a symbol
+    let cipher = Cipher::with_encoding("250210108255", "199", EncodingType::Bytes).unwrap();
+    assert_eq!(cipher.get_encoding_bounds(), 256);
+
+    let plaintext = "Grüße\n\t~";
+    let ciphertext = cipher.encrypt(plaintext).unwrap();
+    let decrypted = cipher.decrypt(&ciphertext).unwrap();
+    assert_eq!(decrypted.trim_end_matches('\0'), plaintext);
+}
+
+#[test]
+fn passphrase_test() {
+    let passphrase = "correct horse battery staple";
+    let credentials = Credentials::from_passphrase(passphrase, "alice", 42).unwrap();
+    assert_eq!(credentials, Credentials::new(&[19, 5, 7, 32], 8));
+
+    let credentials = Credentials::from_passphrase_with_rounds("", "", 256, 0).unwrap();
+    assert_eq!(credentials, Credentials::new(&[205, 4, 19, 236], 176));
+    let credentials = Credentials::from_passphrase_with_rounds(passphrase, "", 42, 1).unwrap();
+    assert_eq!(credentials, Credentials::new(&[15, 18, 18, 12], 27));
+
+    assert_eq!(
+        Credentials::from_passphrase(passphrase, "alice", 257),
+        Err(CipherError::InvalidEncodingSize(257))
+    );
+}
+
+#[test]
+fn key_generation_test() {
+    /// Repeats given numbers
+    struct Sequence(Vec<u64>, usize);
+    impl RandomSource for Sequence {
+        fn next_u64(&mut self) -> u64 {
+            self.1 += 1;
+            self.0[(self.1 - 1) % self.0.len()]
+        }
+    }
+
+    // u64::MAX is rejected, otherwise 0 would be a bit more likely for size 42
+    let mut random = Sequence(vec![u64::MAX, 43, 1, 2, 3, 4], 0);
+    let credentials = Credentials::generate_with(&mut random, 42, false).unwrap();
+    assert_eq!(credentials, Credentials::new(&[1, 1, 2, 3], 4));
+
+    // All-equal key and IV 0 are drawn again
+    let mut random = Sequence(vec![5, 5, 5, 5, 0, 1, 2, 3, 4, 0, 5, 6, 7, 8, 9], 0);
+    let credentials = Credentials::generate_with(&mut random, 42, true).unwrap();
+    assert_eq!(credentials, Credentials::new(&[5, 6, 7, 8], 9));
+
+    assert_eq!(
+        Credentials::generate_with(&mut random, 1, true),
+        Err(CipherError::InvalidEncodingSize(1))
+    );
+
+    for _ in 0..100 {
+        let credentials = Credentials::generate(5).unwrap();
+        assert!(!credentials.is_weak());
+        assert!(credentials.verify_credentials_size(5).is_ok());
+    }
+}
+
+#[test]
+fn credentials_format_test() {
+    let credentials = Credentials::new(&[25, 210, 8, 40], 3);
+    let text = credentials.to_string();
+    assert_eq!(text, "025-210-008-040/003-2");
+    assert_eq!(text.parse(), Ok(credentials));
+    assert_eq!(
+        "025210008040/003-2".parse(),
+        Ok(Credentials::new(&[25, 210, 8, 40], 3))
+    );
+
+    // Every single-digit typo is caught
+    let text = Credentials::new(&[12, 34, 56, 78], 9).to_string();
+    for (i, c) in text.char_indices().filter(|(_, c)| c.is_ascii_digit()) {
+        for typo in ('0'..='9').filter(|&typo| typo != c) {
+            let mut changed = text.clone();
+            changed.replace_range(i..i + 1, &typo.to_string());
+            assert!(changed.parse::<Credentials>().is_err(), "{changed}");
+        }
+    }
+
+    let errors = [
+        ("25-21-08-40", CipherError::MalformedCredentials(11)),
+        ("25-21-08/39-0", CipherError::MalformedCredentials(8)),
+        ("25-21-x8-40/39-0", CipherError::MalformedCredentials(6)),
+        ("25-21-08-400/39-0", CipherError::MalformedCredentials(9)),
+        ("25-21-08-40/39", CipherError::MalformedCredentials(14)),
+        ("25-21-08-40/39-01", CipherError::MalformedCredentials(15)),
+    ];
+    for (text, error) in errors {
+        assert_eq!(text.parse::<Credentials>(), Err(error), "{text}");
+    }
+}
+
+#[test]
+fn key_analysis_test() {
+    let report = analyze(&Credentials::new(&[7, 7, 7, 7], 0), 42).unwrap();
+    assert_eq!(
+        report.weaknesses,
+        vec![Weakness::ZeroIV, Weakness::EqualParts]
+    );
+    // Repeated parts are fine as long as not all of them are equal
+    let repeated = analyze(&Credentials::new(&[7, 3, 7, 9], 1), 42).unwrap();
+    assert!(!repeated.is_weak());
+    assert_eq!(report.cycle_length, 1);
+    assert_eq!(report.equivalent_keys, 42 * 42 * 42 - 1);
+
+    let report = analyze(&Credentials::new(&[25, 21, 18, 40], 37), 42).unwrap();
+    assert!(!report.is_weak());
+    assert_eq!(report.cycle_length, 42);
+
+    // Only the first chunk differs with equivalent credentials
+    let first = Cipher::from(&[25, 21, 18, 40], 37).unwrap();
+    let second = Cipher::from(&[1, 2, 3, 40], 37).unwrap();
+    let plaintext = "attack at dawn, retreat at dusk";
+    let (a, b) = (
+        first.encrypt(plaintext).unwrap(),
+        second.encrypt(plaintext).unwrap(),
+    );
+    assert_ne!(a[..CHUNK_SIZE], b[..CHUNK_SIZE]);
+    assert_eq!(a[CHUNK_SIZE..], b[CHUNK_SIZE..]);
+
+    let keyspace = keyspace(5).unwrap();
+    assert_eq!((keyspace.total, keyspace.classes), (3125, 25));
+}
+
+#[test]
+fn keyring_test() {
+    let file = "# Shared keys\n\nteam = 25-21-18-40/39-9\nfamily = 12350729 / 47 6  RUv5\n";
+    let mut keyring: Keyring = file.parse().unwrap();
+    assert_eq!(keyring.list(), vec!["team", "family"]);
+
+    // Canonical form survives saving and loading
+    let path = std::env::temp_dir().join(format!("tinystorm-keyring-{}", std::process::id()));
+    keyring.save(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "team = 25-21-18-40/39-9 ENv1\nfamily = 12-35-07-29/47-6 RUv5\n"
+    );
+    assert_eq!(Keyring::load(&path).unwrap(), keyring);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        Keyring::load(&path),
+        Err(CipherError::Io(std::io::ErrorKind::NotFound))
+    );
+
+    let ciphertext = keyring.encrypt("team", "hello, world!").unwrap();
+    assert!(ciphertext.starts_with("[kid=team;enc=ENv1;fp="));
+    assert!(ciphertext.ends_with("]yd 0yc ehyrhjgdd"));
+    assert_eq!(
+        keyring.decrypt(&ciphertext).unwrap().trim(),
+        "hello, world!"
+    );
+
+    keyring
+        .add(
+            "work",
+            Credentials::new(&[1, 2, 3, 4], 5),
+            EncodingType::ENv2,
+        )
+        .unwrap();
+    keyring.remove("team").unwrap();
+    assert_eq!(keyring.list(), vec!["family", "work"]);
+    assert_eq!(
+        keyring.decrypt(&ciphertext),
+        Err(CipherError::UnknownKeyId("team".to_owned()))
+    );
+    assert_eq!(
+        keyring.decrypt("yd 0yc ehyrhjgdd"),
+        Err(CipherError::MissingKeyId)
+    );
+
+    let errors = [
+        ("team 25-21-08-40/39-0", CipherError::MalformedKeyring(1)),
+        ("team = 25-21-08-40/39-1", CipherError::MalformedKeyring(1)),
+        (
+            "\nteam = 12-35-07-29/47-6 ENv1",
+            CipherError::MalformedKeyring(2),
+        ),
+        ("t;m = 25-21-08-40/39-0", CipherError::MalformedKeyring(1)),
+        (
+            "a = 25-21-08-40/39-0\na = 25-21-08-40/39-0",
+            CipherError::DuplicateKeyId("a".to_owned()),
+        ),
+    ];
+    for (file, error) in errors {
+        assert_eq!(file.parse::<Keyring>(), Err(error), "{file}");
+    }
+}
+
+#[test]
+fn key_rotation_test() {
+    let keyring: Keyring = "team = 25-21-18-40/39-9".parse().unwrap();
+    let old = keyring.get("team").unwrap();
+    let new = Cipher::with_encoding("01020304", "05", EncodingType::ENv2).unwrap();
+
+    // Key ID is kept, encoding is updated to the new one
+    let ciphertext = keyring.encrypt("team", "hello, world!").unwrap();
+    let rekeyed = rekey(&old, &new, &ciphertext).unwrap();
+    assert!(rekeyed.starts_with("[kid=team;enc=ENv2;fp="));
+    assert_eq!(new.decrypt(&rekeyed).unwrap().trim(), "hello, world!");
+
+    // Plain ciphertext stays plain, symbols are translated from ENv1 to ENv2
+    let ciphertext = old.encrypt("hello, world!").unwrap();
+    let rekeyed = rekey(&old, &new, &ciphertext).unwrap();
+    assert_eq!(rekeyed.len(), ciphertext.len());
+    assert_eq!(
+        rekeyed,
+        new.encrypt("hello, world!").unwrap(),
+        "same as encrypted with the new cipher"
+    );
+
+    // Digits and punctuation exist in RUv5, latin letters don't
+    let russian = Cipher::with_encoding("01020304", "05", EncodingType::RUv5).unwrap();
+    let digits = old.encrypt("2024, 2025!").unwrap();
+    let results = rekey_all(
+        &old,
+        &russian,
+        [ciphertext.as_str(), "[fp=00000000]abcd", digits.as_str()],
+    );
+    assert!(matches!(
+        results[0],
+        Err(CipherError::UntranslatableSymbol(_))
+    ));
+    assert_eq!(results[1], Err(CipherError::EncodingMismatch));
+    assert_eq!(
+        russian
+            .decrypt(results[2].as_ref().unwrap())
+            .unwrap()
+            .trim(),
+        "2024, 2025!"
+    );
+}
+
+#[test]
+fn mnemonic_test() {
+    let credentials = Credentials::new(&[250, 0, 17, 99], 128);
+    let words = to_words(&credentials, Language::Russian).unwrap();
+    assert_eq!(words.split('-').count(), 6);
+    assert_eq!(from_words(&words.to_uppercase()), Ok(credentials.clone()));
+
+    // First 4 letters are always enough
+    let prefixes = words
+        .split('-')
+        .map(|word| word.chars().take(4).collect::<String>())
+        .collect::<Vec<_>>();
+    assert_eq!(from_words(&prefixes.join(" ")), Ok(credentials.clone()));
+
+    // Any single wrong word is caught
+    let words = to_words(&credentials, Language::English).unwrap();
+    let english = to_words(&Credentials::new(&[0, 1, 2, 3], 4), Language::English).unwrap();
+    for i in 0..5 {
+        let mut changed = words.split('-').collect::<Vec<_>>();
+        changed[i] = english.split('-').nth(i).unwrap();
+        assert_eq!(
+            from_words(&changed.join("-")),
+            Err(CipherError::InvalidChecksum)
+        );
+    }
+
+    // Any swap of two different words is caught
+    let words = to_words(&Credentials::new(&[0, 1, 2, 3], 32), Language::English).unwrap();
+    let words = words.split('-').collect::<Vec<_>>();
+    for i in 0..6 {
+        for j in i + 1..6 {
+            let mut swapped = words.clone();
+            swapped.swap(i, j);
+            assert_eq!(
+                from_words(&swapped.join("-")),
+                Err(CipherError::InvalidChecksum),
+                "{i} <-> {j}"
+            );
+        }
+    }
+
+    assert_eq!(
+        from_words("able able able able able"),
+        Err(CipherError::InvalidKey)
+    );
+    assert_eq!(
+        from_words("able able able xyzzy able able"),
+        Err(CipherError::UnknownWord("xyzzy".to_owned()))
+    );
+    assert_eq!(
+        from_words("able able a able able able"),
+        Err(CipherError::AmbiguousWord("a".to_owned()))
+    );
+}
+
+#[test]
+fn credentials_redaction_test() {
+    let credentials = Credentials::new(&[25, 21, 18, 40], 39);
+    let cipher = Cipher::with_credentials(credentials.clone(), EncodingType::ENv1).unwrap();
+
+    let debug = format!("{cipher:?}");
+    assert!(debug.contains("Credentials { key: <redacted>, iv: <redacted> }"));
+    assert!(!debug.contains("25, 21"));
+    assert_eq!(credentials.expose(), (&[25, 21, 18, 40][..], 39));
+
+    let mut buffer = vec![1, 2, 3];
+    wipe(&mut buffer);
+    assert_eq!(buffer, vec![0, 0, 0]);
+}
+
+#[test]
+fn key_exchange_test() {
+    let transcript = Transcript::run(Group::TEXTBOOK, 4, 3, 42).unwrap();
+    assert_eq!((transcript.alice_public, transcript.bob_public), (4, 10));
+    assert_eq!(transcript.shared_secret, 18);
+    assert_eq!(transcript.credentials, derive_credentials(18, 42).unwrap());
+
+    let printed = transcript.to_string();
+    assert_eq!(printed.lines().count(), 6);
+    assert!(printed.contains("A = g^a mod p = 5^4 mod 23 = 4"));
+    assert!(printed.ends_with(&transcript.credentials.to_string()));
+
+    // Credentials fit the encoding and work for both sides
+    let alice = Party::new(Group::CLASSROOM, 57).unwrap();
+    let bob = Party::new(Group::CLASSROOM, 198).unwrap();
+    let credentials = alice.credentials(bob.public_key(), 70).unwrap();
+    assert_eq!(
+        Ok(credentials.clone()),
+        bob.credentials(alice.public_key(), 70)
+    );
+    let cipher = Cipher::with_credentials(credentials, EncodingType::ENv2).unwrap();
+    let ciphertext = cipher.encrypt("Meet at noon").unwrap();
+    assert_eq!(cipher.decrypt(&ciphertext).unwrap().trim(), "Meet at noon");
+
+    assert_eq!(Group::new(21, 2), Err(CipherError::InvalidGroup));
+    assert_eq!(Group::new(23, 5), Ok(Group::TEXTBOOK));
+    // 5^11 mod 23 = 22, so the public key would be prime - 1
+    assert_eq!(
+        Party::new(Group::TEXTBOOK, 11),
+        Err(CipherError::InvalidExchangeKey(11))
+    );
+    assert_eq!(
+        alice.shared_secret(1),
+        Err(CipherError::InvalidExchangeKey(1))
+    );
+    assert_eq!(
+        (Group::CLASSROOM.prime(), Group::CLASSROOM.generator()),
+        (227, 2)
+    );
+    assert!(format!("{alice:?}").contains("secret: <redacted>"));
+}
+
 #[test]
 fn key_expansion_test() {
     let size = 4;
@@ -69,7 +951,7 @@ fn key_expansion_test() {
 
 #[test]
 fn chunks_adjust_test() {
-    // Vec is critical here since adjust_chunks uses unsafe code for maximum performance
+    // adjust_chunks grows the Vec in place, so it must be a Vec and not a slice
     let mut buffer = vec![1, 2, 3, 4, 5];
     let remainder = buffer.len() % CHUNK_SIZE;
 
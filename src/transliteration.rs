@@ -0,0 +1,233 @@
+//! Transliteration between Cyrillic and Latin scripts.
+//! Rules are based on GOST 7.79-2000 System B (which is compatible with ISO 9 and uses only ASCII)
+
+/// Transliteration applied by [`Encoder`] to chars that don't exist in its table.
+/// Chars are replaced only if the whole replacement can be encoded. GOST forms with backticks
+/// fall back to the ones without them for tables which don't have backticks
+///
+/// [`Encoder`]: crate::encoding::Encoder
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Transliteration {
+    /// Chars are left as is, unknown chars are removed by encoder
+    #[default]
+    Off,
+    /// GOST 7.79 System B rules: 'ж' -> "zh" for latin tables and "zh" -> 'ж' for cyrillic ones
+    Gost,
+}
+
+/// Cyrillic (russian, ukrainian and belarusian) letters in GOST 7.79 System B
+const CYRILLIC_TO_LATIN: &[(char, &str)] = &[
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "yo"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('й', "j"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ф', "f"),
+    ('х', "x"),
+    ('ц', "cz"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shh"),
+    ('ъ', "``"),
+    ('ы', "y`"),
+    ('ь', "`"),
+    ('э', "e`"),
+    ('ю', "yu"),
+    ('я', "ya"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('є', "ye"),
+    ('ґ', "g`"),
+    ('ў', "u`"),
+];
+
+/// Latin letters and sequences which are not in [`CYRILLIC_TO_LATIN`],
+/// but can be found in english text
+const LATIN_FALLBACK: &[(&str, char)] =
+    &[("c", 'ц'), ("h", 'х'), ("q", 'к'), ("w", 'в'), ("y", 'ы')];
+
+/// Returns latin transliteration of cyrillic char, keeping its case
+/// # Example
+/// ```
+/// use tinystorm::transliteration::cyrillic_to_latin;
+///
+/// assert_eq!(cyrillic_to_latin('ж'), Some("zh".to_owned()));
+/// assert_eq!(cyrillic_to_latin('Щ'), Some("Shh".to_owned()));
+/// assert_eq!(cyrillic_to_latin('z'), None);
+/// ```
+pub fn cyrillic_to_latin(c: char) -> Option<String> {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    let &(_, latin) = CYRILLIC_TO_LATIN.iter().find(|&&(ch, _)| ch == lower)?;
+
+    Some(if lower != c {
+        capitalize(latin)
+    } else {
+        latin.to_owned()
+    })
+}
+
+/// Find the longest latin sequence at the start of `chars` that has cyrillic transliteration.
+/// Returns cyrillic char (keeping case of the first latin char) and length of the sequence
+/// # Example
+/// ```
+/// use tinystorm::transliteration::latin_to_cyrillic;
+///
+/// let chars = "Shhuka".chars().collect::<Vec<_>>();
+/// assert_eq!(latin_to_cyrillic(&chars), Some(('Щ', 3)));
+/// ```
+pub fn latin_to_cyrillic(chars: &[char]) -> Option<(char, usize)> {
+    let first = *chars.first()?;
+
+    let sequences = CYRILLIC_TO_LATIN
+        .iter()
+        .map(|&(cyrillic, latin)| (latin, cyrillic))
+        .chain(LATIN_FALLBACK.iter().copied());
+
+    // Ukrainian letters share latin sequences with russian ones, so the first match is kept
+    let mut best: Option<(char, usize)> = None;
+    for (latin, cyrillic) in sequences {
+        let len = latin.chars().count();
+        let matches = chars.len() >= len
+            && latin
+                .chars()
+                .zip(chars)
+                .all(|(l, &c)| c.to_lowercase().eq(std::iter::once(l)));
+
+        if matches && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((cyrillic, len));
+        }
+    }
+
+    best.map(|(cyrillic, len)| {
+        let cyrillic = if first.is_uppercase() {
+            cyrillic.to_uppercase().next().unwrap_or(cyrillic)
+        } else {
+            cyrillic
+        };
+        (cyrillic, len)
+    })
+}
+
+/// Best-effort conversion of cyrillic text to latin, for displaying purposes
+/// # Example
+/// ```
+/// use tinystorm::transliteration::to_latin;
+///
+/// assert_eq!(to_latin("Жук, 1!"), "Zhuk, 1!");
+/// ```
+pub fn to_latin(text: &str) -> String {
+    text.chars()
+        .map(|c| cyrillic_to_latin(c).unwrap_or_else(|| c.to_string()))
+        .collect()
+}
+
+/// Best-effort conversion of latin text to cyrillic, for displaying purposes
+/// # Example
+/// ```
+/// use tinystorm::transliteration::to_cyrillic;
+///
+/// assert_eq!(to_cyrillic("Zhuk, 1!"), "Жук, 1!");
+/// ```
+pub fn to_cyrillic(text: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(text.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        match latin_to_cyrillic(&chars[i..]) {
+            Some((c, len)) => {
+                result.push(c);
+                i += len;
+            }
+            None => {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// Transliterate chars of `text` for which `is_known` returns false.
+/// Replacement is used only if all of its chars are known
+pub(crate) fn transliterate(
+    text: &str,
+    transliteration: Transliteration,
+    is_known: impl Fn(char) -> bool,
+) -> String {
+    if transliteration == Transliteration::Off {
+        return text.to_owned();
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(text.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_known(c) {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+
+        if let Some(latin) = latin_replacement(c, &is_known) {
+            result.push_str(&latin);
+            i += 1;
+            continue;
+        }
+
+        // Latin sequence is matched as a whole, even if some of its chars are known
+        match latin_to_cyrillic(&chars[i..]).filter(|&(cyrillic, _)| is_known(cyrillic)) {
+            Some((cyrillic, len)) => {
+                result.push(cyrillic);
+                i += len;
+            }
+            None => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// Latin replacement of cyrillic char whose chars are all known. Falls back to the form
+/// without backticks (ы -> "y", э -> "e"), soft and hard signs become an apostrophe
+fn latin_replacement(c: char, is_known: impl Fn(char) -> bool) -> Option<String> {
+    let latin = cyrillic_to_latin(c)?;
+    let simplified = match latin.replace('`', "") {
+        simplified if simplified.is_empty() => "'".to_owned(),
+        simplified => simplified,
+    };
+
+    [latin, simplified]
+        .into_iter()
+        .find(|replacement| replacement.chars().all(&is_known))
+}
+
+fn capitalize(str: &str) -> String {
+    let mut chars = str.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}